@@ -134,8 +134,12 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 
 use frame_system::ensure_signed;
-use frame_support::{Parameter, decl_module, decl_event, decl_storage, decl_error, ensure};
-use sp_runtime::traits::{Member, AtLeast32Bit, AtLeast32BitUnsigned, One, Zero, StaticLookup, MaybeSerializeDeserialize};
+use frame_support::{Parameter, decl_module, decl_event, decl_storage, decl_error, ensure, dispatch::DispatchResult};
+use sp_runtime::DispatchError;
+use sp_runtime::traits::{
+	Member, AtLeast32Bit, AtLeast32BitUnsigned, One, Zero, StaticLookup, MaybeSerializeDeserialize,
+	CheckedAdd, CheckedSub,
+};
 
 /// The module configuration trait.
 pub trait Config: frame_system::Config {
@@ -147,6 +151,21 @@ pub trait Config: frame_system::Config {
 
 	/// The arithmetic type of asset identifier.
 	type AssetId: Parameter + AtLeast32Bit + Default + Copy + MaybeSerializeDeserialize;
+
+	/// Decides whether units of an asset may currently be transferred.
+	type TransferGuard: TransferGuard<Self::AssetId, Self::AccountId>;
+}
+
+/// A check run before any units of an asset change hands.
+pub trait TransferGuard<AssetId, AccountId> {
+	/// Fail if units of asset `id` may not be moved from `from` to `to`.
+	fn ensure_can_transfer(id: AssetId, from: &AccountId, to: &AccountId) -> DispatchResult;
+}
+
+impl<AssetId, AccountId> TransferGuard<AssetId, AccountId> for () {
+	fn ensure_can_transfer(_id: AssetId, _from: &AccountId, _to: &AccountId) -> DispatchResult {
+		Ok(())
+	}
 }
 
 decl_module! {
@@ -169,7 +188,8 @@ decl_module! {
 			let origin = ensure_signed(origin)?;
 
 			let id = Self::next_asset_id();
-			<NextAssetId<T>>::mutate(|id| *id += One::one());
+			let next_id = id.checked_add(&One::one()).ok_or(Error::<T>::Overflow)?;
+			<NextAssetId<T>>::put(next_id);
 
 			<Balances<T>>::insert((id, &origin), total);
 			<TotalSupply<T>>::insert(id, total);
@@ -186,7 +206,7 @@ decl_module! {
 		/// - 1 event.
 		/// # </weight>
 		#[weight = 0]
-		fn transfer(origin,
+		pub fn transfer(origin,
 			#[compact] id: T::AssetId,
 			target: <T::Lookup as StaticLookup>::Source,
 			#[compact] amount: T::Balance
@@ -198,9 +218,8 @@ decl_module! {
 			ensure!(!amount.is_zero(), Error::<T>::AmountZero);
 			ensure!(origin_balance >= amount, Error::<T>::BalanceLow);
 
-			Self::deposit_event(RawEvent::Transferred(id, origin, target.clone(), amount));
-			<Balances<T>>::insert(origin_account, origin_balance - amount);
-			<Balances<T>>::mutate((id, target), |balance| *balance += amount);
+			Self::make_transfer(id, origin.clone(), target.clone(), amount)?;
+			Self::deposit_event(RawEvent::Transferred(id, origin, target, amount));
 		}
 
 		/// Destroy any assets of `id` owned by `origin`.
@@ -214,10 +233,12 @@ decl_module! {
 		#[weight = 0]
 		fn destroy(origin, #[compact] id: T::AssetId) {
 			let origin = ensure_signed(origin)?;
-			let balance = <Balances<T>>::take((id, &origin));
+			let balance = <Balances<T>>::get((id, &origin));
 			ensure!(!balance.is_zero(), Error::<T>::BalanceZero);
+			let new_total = Self::total_supply(id).checked_sub(&balance).ok_or(Error::<T>::Underflow)?;
 
-			<TotalSupply<T>>::mutate(id, |total_supply| *total_supply -= balance);
+			<Balances<T>>::remove((id, &origin));
+			<TotalSupply<T>>::insert(id, new_total);
 			Self::deposit_event(RawEvent::Destroyed(id, origin, balance));
 		}
 	}
@@ -246,6 +267,12 @@ decl_error! {
 		BalanceLow,
 		/// Balance should be non-zero
 		BalanceZero,
+		/// An addition overflowed the balance type
+		Overflow,
+		/// A subtraction went below zero
+		Underflow,
+		/// The next asset identifier was already minted into
+		InUse,
 	}
 }
 
@@ -276,16 +303,58 @@ impl<T: Config> Module<T> {
 		<TotalSupply<T>>::get(id)
 	}
 
-	pub fn mint(id: T::AssetId, who: T::AccountId, amount: T::Balance) {
+	/// Whether asset `id` has ever been issued or minted.
+	pub fn asset_exists(id: T::AssetId) -> bool {
+		<TotalSupply<T>>::contains_key(id)
+	}
+
+	/// Register a new asset with an empty supply and return its identifier.
+	pub fn create_asset() -> Result<T::AssetId, DispatchError> {
+		let id = Self::next_asset_id();
+		ensure!(!Self::asset_exists(id), Error::<T>::InUse);
+		let next_id = id.checked_add(&One::one()).ok_or(Error::<T>::Overflow)?;
+
+		<NextAssetId<T>>::put(next_id);
+		<TotalSupply<T>>::insert(id, T::Balance::zero());
+		Ok(id)
+	}
+
+	/// Create `amount` new units of asset `id` in the account of `who`.
+	pub fn mint(id: T::AssetId, who: T::AccountId, amount: T::Balance) -> DispatchResult {
 		// Self::deposit_event(RawEvent::Issued(id, who.clone(), amount));
-		<Balances<T>>::mutate((id, who), |balance| *balance += amount);
-		<TotalSupply<T>>::mutate(id, |total| *total += amount);
+		let new_total = Self::total_supply(id).checked_add(&amount).ok_or(Error::<T>::Overflow)?;
+		let new_balance = Self::balance(id, who.clone()).checked_add(&amount).ok_or(Error::<T>::Overflow)?;
+
+		<Balances<T>>::insert((id, who), new_balance);
+		<TotalSupply<T>>::insert(id, new_total);
+		Ok(())
 	}
 
-	pub fn burn(id: T::AssetId, who: T::AccountId, amount: T::Balance) {
+	/// Remove `amount` units of asset `id` from the account of `who`.
+	pub fn burn(id: T::AssetId, who: T::AccountId, amount: T::Balance) -> DispatchResult {
 		// Self::deposit_event(RawEvent::Destroyed(id, who.clone(), amount));
-		<Balances<T>>::mutate((id, who), |balance| *balance -= amount);
-		<TotalSupply<T>>::mutate(id, |total| *total -= amount);
+		let new_balance = Self::balance(id, who.clone()).checked_sub(&amount).ok_or(Error::<T>::Underflow)?;
+		let new_total = Self::total_supply(id).checked_sub(&amount).ok_or(Error::<T>::Underflow)?;
+
+		<Balances<T>>::insert((id, who), new_balance);
+		<TotalSupply<T>>::insert(id, new_total);
+		Ok(())
+	}
+
+	/// Move `amount` of asset `id` from `from` to `to` without touching the total supply.
+	pub fn make_transfer(id: T::AssetId, from: T::AccountId, to: T::AccountId, amount: T::Balance) -> DispatchResult {
+		T::TransferGuard::ensure_can_transfer(id, &from, &to)?;
+		if from == to {
+			ensure!(Self::balance(id, from) >= amount, Error::<T>::BalanceLow);
+			return Ok(());
+		}
+		let from_balance = Self::balance(id, from.clone());
+		ensure!(from_balance >= amount, Error::<T>::BalanceLow);
+		let new_to_balance = Self::balance(id, to.clone()).checked_add(&amount).ok_or(Error::<T>::Overflow)?;
+
+		<Balances<T>>::insert((id, from), from_balance - amount);
+		<Balances<T>>::insert((id, to), new_to_balance);
+		Ok(())
 	}
 }
 
@@ -346,6 +415,7 @@ mod tests {
 		type Event = ();
 		type Balance = u64;
 		type AssetId = u32;
+		type TransferGuard = ();
 	}
 
 	fn new_test_ext() -> sp_io::TestExternalities {
@@ -429,6 +499,51 @@ mod tests {
 		});
 	}
 
+	#[test]
+	fn minting_past_the_balance_type_should_not_work() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(Assets::mint(0, 1, u64::max_value()));
+			assert_noop!(Assets::mint(0, 2, 1), Error::<Test>::Overflow);
+			assert_noop!(Assets::mint(0, 1, 1), Error::<Test>::Overflow);
+			assert_eq!(Assets::total_supply(0), u64::max_value());
+		});
+	}
+
+	#[test]
+	fn creating_an_asset_should_work() {
+		new_test_ext().execute_with(|| {
+			assert_eq!(Assets::create_asset(), Ok(0));
+			assert!(Assets::asset_exists(0));
+			assert_eq!(Assets::total_supply(0), 0);
+			assert_ok!(Assets::issue(Origin::signed(1), 100));
+			assert_eq!(Assets::balance(1, 1), 100);
+			// An id which was minted into directly is not handed out again.
+			assert_ok!(Assets::mint(2, 1, 10));
+			assert_noop!(Assets::create_asset(), Error::<Test>::InUse);
+		});
+	}
+
+	#[test]
+	fn burning_more_than_balance_should_not_work() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(Assets::mint(0, 1, 10));
+			assert_noop!(Assets::burn(0, 1, 11), Error::<Test>::Underflow);
+			assert_ok!(Assets::burn(0, 1, 10));
+			assert_eq!(Assets::balance(0, 1), 0);
+			assert_eq!(Assets::total_supply(0), 0);
+		});
+	}
+
+	#[test]
+	fn transferring_into_a_full_account_should_not_work() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(Assets::issue(Origin::signed(1), 10));
+			// Credit account 2 directly so the sum of balances exceeds the supply bound.
+			<Balances<Test>>::insert((0, 2), u64::max_value());
+			assert_noop!(Assets::transfer(Origin::signed(1), 0, 2, 1), Error::<Test>::Overflow);
+		});
+	}
+
 	#[test]
 	fn destroying_asset_balance_with_zero_balance_should_not_work() {
 		new_test_ext().execute_with(|| {
@@ -0,0 +1,372 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+//! A constant-product exchange between assets of `pallet_assets`.
+//!
+//! Every pool holds two assets in its own account and issues an LP asset to its liquidity
+//! providers. Swaps charge `ExchangeFee` on their input, which stays in the pool.
+
+use frame_support::{
+    decl_error, decl_event, decl_module, decl_storage, ensure, transactional,
+    dispatch::DispatchResult, traits::Get,
+};
+use frame_system::ensure_signed;
+use sp_runtime::{
+    DispatchError, ModuleId, Permill, PerThing, SaturatedConversion,
+    helpers_128bit::multiply_by_rational,
+    traits::{AccountIdConversion, CheckedAdd, IntegerSquareRoot, One, Saturating, Zero},
+};
+use sp_std::convert::TryFrom;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+pub trait Config: pallet_assets::Config {
+    type Event: From<Event<Self>> + Into<<Self as frame_system::Config>::Event>;
+    /// The pallet id, used for deriving the account of each pool.
+    type ModuleId: Get<ModuleId>;
+    /// The share of every swap input kept by the pool.
+    type ExchangeFee: Get<Permill>;
+    /// The LP units locked in a pool by its first deposit, so its supply never returns to zero.
+    type MinimumLiquidity: Get<Self::Balance>;
+}
+
+decl_storage! {
+    trait Store for Module<T: Config> as Dex {
+        /// The LP asset of the pool trading a pair of assets, keyed by the pair in ascending order.
+        Pools get(fn pools): map hasher(blake2_128_concat) (T::AssetId, T::AssetId) => Option<T::AssetId>;
+    }
+}
+
+decl_event!(
+    pub enum Event<T>
+    where
+        AccountId = <T as frame_system::Config>::AccountId,
+        AssetId = <T as pallet_assets::Config>::AssetId,
+        Balance = <T as pallet_assets::Config>::Balance,
+    {
+        /// A pool was created. \[asset_a, asset_b, lp_asset, who\]
+        PoolCreated(AssetId, AssetId, AssetId, AccountId),
+        /// Liquidity was deposited. \[who, asset_a, asset_b, amount_a, amount_b, liquidity\]
+        LiquidityAdded(AccountId, AssetId, AssetId, Balance, Balance, Balance),
+        /// Liquidity was withdrawn. \[who, asset_a, asset_b, amount_a, amount_b, liquidity\]
+        LiquidityRemoved(AccountId, AssetId, AssetId, Balance, Balance, Balance),
+        /// Assets were swapped. \[who, asset_in, asset_out, amount_in, amount_out\]
+        Swapped(AccountId, AssetId, AssetId, Balance, Balance),
+    }
+);
+
+decl_error! {
+    pub enum Error for Module<T: Config> {
+        /// A pool must trade two different assets.
+        IdenticalAssets,
+        /// The asset was never issued.
+        UnknownAsset,
+        /// A pool for the pair already exists.
+        PoolAlreadyExists,
+        /// There is no pool for the pair.
+        PoolNotExist,
+        /// The amount must be non-zero.
+        ZeroAmount,
+        /// The pool reserves cannot cover the trade.
+        InsufficientLiquidity,
+        /// The deposit is too small to mint any LP units.
+        InsufficientLiquidityMinted,
+        /// The trade is worse than the limit set by the caller.
+        SlippageExceeded,
+        /// An arithmetic operation overflowed.
+        Overflow,
+    }
+}
+
+decl_module! {
+    pub struct Module<T: Config> for enum Call where origin: T::Origin {
+        type Error = Error<T>;
+
+        fn deposit_event() = default;
+
+        /// The share of every swap input kept by the pool.
+        const ExchangeFee: Permill = T::ExchangeFee::get();
+
+        /// The LP units locked in a pool by its first deposit.
+        const MinimumLiquidity: T::Balance = T::MinimumLiquidity::get();
+
+        /// Create an empty pool trading `asset_a` against `asset_b`, along with its LP asset.
+        #[weight = 10_000 + T::DbWeight::get().reads_writes(4, 3)]
+        pub fn create_pool(origin, asset_a: T::AssetId, asset_b: T::AssetId) {
+            let who = ensure_signed(origin)?;
+            ensure!(asset_a != asset_b, Error::<T>::IdenticalAssets);
+            ensure!(
+                pallet_assets::Module::<T>::asset_exists(asset_a) && pallet_assets::Module::<T>::asset_exists(asset_b),
+                Error::<T>::UnknownAsset
+            );
+            let key = Self::pool_key(asset_a, asset_b);
+            ensure!(!<Pools<T>>::contains_key(&key), Error::<T>::PoolAlreadyExists);
+
+            let lp_asset = pallet_assets::Module::<T>::create_asset()?;
+            <Pools<T>>::insert(&key, lp_asset);
+            Self::deposit_event(RawEvent::PoolCreated(asset_a, asset_b, lp_asset, who));
+        }
+
+        /// Deposit at most `amount_a` and `amount_b` at the current pool price for at least
+        /// `min_liquidity` LP units. The first deposit sets the price.
+        #[weight = 10_000 + T::DbWeight::get().reads_writes(6, 6)]
+        #[transactional]
+        pub fn add_liquidity(origin,
+            asset_a: T::AssetId,
+            asset_b: T::AssetId,
+            #[compact] amount_a: T::Balance,
+            #[compact] amount_b: T::Balance,
+            #[compact] min_liquidity: T::Balance
+        ) {
+            let who = ensure_signed(origin)?;
+            ensure!(!amount_a.is_zero() && !amount_b.is_zero(), Error::<T>::ZeroAmount);
+            let lp_asset = Self::pool_asset(asset_a, asset_b)?;
+            let pool = Self::pool_account(lp_asset);
+            let reserve_a = Self::reserve(asset_a, &pool);
+            let reserve_b = Self::reserve(asset_b, &pool);
+            let supply = pallet_assets::Module::<T>::total_supply(lp_asset);
+
+            let (amount_a, amount_b, liquidity) = if supply.is_zero() {
+                let liquidity = Self::initial_liquidity(amount_a, amount_b)?;
+                let locked = T::MinimumLiquidity::get();
+                ensure!(liquidity > locked, Error::<T>::InsufficientLiquidityMinted);
+                pallet_assets::Module::<T>::mint(lp_asset, pool.clone(), locked)?;
+                (amount_a, amount_b, liquidity - locked)
+            } else {
+                ensure!(!reserve_a.is_zero() && !reserve_b.is_zero(), Error::<T>::InsufficientLiquidity);
+                let optimal_b = Self::mul_div(amount_a, reserve_b, reserve_a)?;
+                let (amount_a, amount_b) = if optimal_b <= amount_b {
+                    (amount_a, optimal_b)
+                } else {
+                    (Self::mul_div(amount_b, reserve_a, reserve_b)?, amount_b)
+                };
+                let liquidity = Self::mul_div(amount_a, supply, reserve_a)?
+                    .min(Self::mul_div(amount_b, supply, reserve_b)?);
+                (amount_a, amount_b, liquidity)
+            };
+            ensure!(!liquidity.is_zero(), Error::<T>::InsufficientLiquidityMinted);
+            ensure!(liquidity >= min_liquidity, Error::<T>::SlippageExceeded);
+
+            pallet_assets::Module::<T>::make_transfer(asset_a, who.clone(), pool.clone(), amount_a)?;
+            pallet_assets::Module::<T>::make_transfer(asset_b, who.clone(), pool, amount_b)?;
+            pallet_assets::Module::<T>::mint(lp_asset, who.clone(), liquidity)?;
+            Self::deposit_event(RawEvent::LiquidityAdded(who, asset_a, asset_b, amount_a, amount_b, liquidity));
+        }
+
+        /// Burn `liquidity` LP units for a pro-rata share of both reserves, at least `min_a`
+        /// and `min_b`.
+        #[weight = 10_000 + T::DbWeight::get().reads_writes(6, 6)]
+        #[transactional]
+        pub fn remove_liquidity(origin,
+            asset_a: T::AssetId,
+            asset_b: T::AssetId,
+            #[compact] liquidity: T::Balance,
+            #[compact] min_a: T::Balance,
+            #[compact] min_b: T::Balance
+        ) {
+            let who = ensure_signed(origin)?;
+            ensure!(!liquidity.is_zero(), Error::<T>::ZeroAmount);
+            let lp_asset = Self::pool_asset(asset_a, asset_b)?;
+            let pool = Self::pool_account(lp_asset);
+            let supply = pallet_assets::Module::<T>::total_supply(lp_asset);
+            ensure!(!supply.is_zero(), Error::<T>::InsufficientLiquidity);
+
+            let amount_a = Self::mul_div(liquidity, Self::reserve(asset_a, &pool), supply)?;
+            let amount_b = Self::mul_div(liquidity, Self::reserve(asset_b, &pool), supply)?;
+            ensure!(amount_a >= min_a && amount_b >= min_b, Error::<T>::SlippageExceeded);
+
+            pallet_assets::Module::<T>::burn(lp_asset, who.clone(), liquidity)?;
+            pallet_assets::Module::<T>::make_transfer(asset_a, pool.clone(), who.clone(), amount_a)?;
+            pallet_assets::Module::<T>::make_transfer(asset_b, pool, who.clone(), amount_b)?;
+            Self::deposit_event(RawEvent::LiquidityRemoved(who, asset_a, asset_b, amount_a, amount_b, liquidity));
+        }
+
+        /// Swap exactly `amount_in` of `asset_in` for at least `min_amount_out` of `asset_out`.
+        #[weight = 10_000 + T::DbWeight::get().reads_writes(5, 4)]
+        pub fn swap_exact_in(origin,
+            asset_in: T::AssetId,
+            asset_out: T::AssetId,
+            #[compact] amount_in: T::Balance,
+            #[compact] min_amount_out: T::Balance
+        ) {
+            let who = ensure_signed(origin)?;
+            Self::do_swap_exact_in(&who, asset_in, asset_out, amount_in, min_amount_out)?;
+        }
+
+        /// Swap at most `max_amount_in` of `asset_in` for exactly `amount_out` of `asset_out`.
+        #[weight = 10_000 + T::DbWeight::get().reads_writes(5, 4)]
+        pub fn swap_exact_out(origin,
+            asset_in: T::AssetId,
+            asset_out: T::AssetId,
+            #[compact] amount_out: T::Balance,
+            #[compact] max_amount_in: T::Balance
+        ) {
+            let who = ensure_signed(origin)?;
+            Self::do_swap_exact_out(&who, asset_in, asset_out, amount_out, max_amount_in)?;
+        }
+    }
+}
+
+impl<T: Config> Module<T> {
+    /// The account holding the reserves of the pool issuing `lp_asset`.
+    pub fn pool_account(lp_asset: T::AssetId) -> T::AccountId {
+        T::ModuleId::get().into_sub_account(lp_asset)
+    }
+
+    /// The LP asset of the pool trading `asset_a` against `asset_b`.
+    pub fn pool_asset(asset_a: T::AssetId, asset_b: T::AssetId) -> Result<T::AssetId, DispatchError> {
+        Self::pools(Self::pool_key(asset_a, asset_b)).ok_or_else(|| Error::<T>::PoolNotExist.into())
+    }
+
+    /// The amount of `asset_out` that swapping `amount_in` of `asset_in` currently yields.
+    pub fn quote_exact_in(asset_in: T::AssetId, asset_out: T::AssetId, amount_in: T::Balance) -> Result<T::Balance, DispatchError> {
+        let pool = Self::pool_account(Self::pool_asset(asset_in, asset_out)?);
+        Self::get_amount_out(amount_in, Self::reserve(asset_in, &pool), Self::reserve(asset_out, &pool))
+    }
+
+    /// The amount of `asset_in` currently needed to receive `amount_out` of `asset_out`.
+    pub fn quote_exact_out(asset_in: T::AssetId, asset_out: T::AssetId, amount_out: T::Balance) -> Result<T::Balance, DispatchError> {
+        let pool = Self::pool_account(Self::pool_asset(asset_in, asset_out)?);
+        Self::get_amount_in(amount_out, Self::reserve(asset_in, &pool), Self::reserve(asset_out, &pool))
+    }
+
+    /// Swap exactly `amount_in` of `asset_in` from `who` for at least `min_amount_out` of
+    /// `asset_out`, returning the amount received.
+    #[transactional]
+    pub fn do_swap_exact_in(
+        who: &T::AccountId,
+        asset_in: T::AssetId,
+        asset_out: T::AssetId,
+        amount_in: T::Balance,
+        min_amount_out: T::Balance,
+    ) -> Result<T::Balance, DispatchError> {
+        let amount_out = Self::quote_exact_in(asset_in, asset_out, amount_in)?;
+        ensure!(!amount_out.is_zero(), Error::<T>::ZeroAmount);
+        ensure!(amount_out >= min_amount_out, Error::<T>::SlippageExceeded);
+
+        Self::swap(who, asset_in, asset_out, amount_in, amount_out)?;
+        Ok(amount_out)
+    }
+
+    /// Swap at most `max_amount_in` of `asset_in` from `who` for exactly `amount_out` of
+    /// `asset_out`, returning the amount paid.
+    #[transactional]
+    pub fn do_swap_exact_out(
+        who: &T::AccountId,
+        asset_in: T::AssetId,
+        asset_out: T::AssetId,
+        amount_out: T::Balance,
+        max_amount_in: T::Balance,
+    ) -> Result<T::Balance, DispatchError> {
+        let amount_in = Self::quote_exact_out(asset_in, asset_out, amount_out)?;
+        ensure!(amount_in <= max_amount_in, Error::<T>::SlippageExceeded);
+
+        Self::swap(who, asset_in, asset_out, amount_in, amount_out)?;
+        Ok(amount_in)
+    }
+
+    /// The output of a trade against reserves `reserve_in` and `reserve_out`, after the fee.
+    pub fn get_amount_out(amount_in: T::Balance, reserve_in: T::Balance, reserve_out: T::Balance) -> Result<T::Balance, DispatchError> {
+        ensure!(!amount_in.is_zero(), Error::<T>::ZeroAmount);
+        ensure!(!reserve_in.is_zero() && !reserve_out.is_zero(), Error::<T>::InsufficientLiquidity);
+
+        let net_in = Permill::one().saturating_sub(T::ExchangeFee::get()).mul_floor(amount_in);
+        let denominator = reserve_in.checked_add(&net_in).ok_or(Error::<T>::Overflow)?;
+        Self::mul_div(net_in, reserve_out, denominator)
+    }
+
+    /// The input, fee included, a trade against reserves `reserve_in` and `reserve_out` needs
+    /// to yield `amount_out`.
+    pub fn get_amount_in(amount_out: T::Balance, reserve_in: T::Balance, reserve_out: T::Balance) -> Result<T::Balance, DispatchError> {
+        ensure!(!amount_out.is_zero(), Error::<T>::ZeroAmount);
+        ensure!(!reserve_in.is_zero() && amount_out < reserve_out, Error::<T>::InsufficientLiquidity);
+
+        // One more than the input which would leave the product of the reserves unchanged,
+        // so rounding always favours the pool.
+        let net_in = Self::mul_div(reserve_in, amount_out, reserve_out - amount_out)?
+            .checked_add(&One::one())
+            .ok_or(Error::<T>::Overflow)?;
+        let keep = Permill::one().saturating_sub(T::ExchangeFee::get());
+        let mut amount_in = Self::mul_div(net_in, Permill::ACCURACY.into(), keep.deconstruct().into())?;
+        if keep.mul_floor(amount_in) < net_in {
+            amount_in = amount_in.checked_add(&One::one()).ok_or(Error::<T>::Overflow)?;
+        }
+        Ok(amount_in)
+    }
+
+    fn swap(
+        who: &T::AccountId,
+        asset_in: T::AssetId,
+        asset_out: T::AssetId,
+        amount_in: T::Balance,
+        amount_out: T::Balance,
+    ) -> DispatchResult {
+        let pool = Self::pool_account(Self::pool_asset(asset_in, asset_out)?);
+        pallet_assets::Module::<T>::make_transfer(asset_in, who.clone(), pool.clone(), amount_in)?;
+        pallet_assets::Module::<T>::make_transfer(asset_out, pool, who.clone(), amount_out)?;
+        Self::deposit_event(RawEvent::Swapped(who.clone(), asset_in, asset_out, amount_in, amount_out));
+        Ok(())
+    }
+
+    fn pool_key(asset_a: T::AssetId, asset_b: T::AssetId) -> (T::AssetId, T::AssetId) {
+        if asset_a < asset_b { (asset_a, asset_b) } else { (asset_b, asset_a) }
+    }
+
+    fn reserve(asset_id: T::AssetId, pool: &T::AccountId) -> T::Balance {
+        pallet_assets::Module::<T>::balance(asset_id, pool.clone())
+    }
+
+    // The geometric mean of the first deposit, so the LP supply does not depend on the price.
+    fn initial_liquidity(amount_a: T::Balance, amount_b: T::Balance) -> Result<T::Balance, DispatchError> {
+        let root = sqrt_of_product(amount_a.saturated_into(), amount_b.saturated_into());
+        T::Balance::try_from(root).map_err(|_| Error::<T>::Overflow.into())
+    }
+
+    // `a * b / c`, rounded down, without overflowing on the intermediate product.
+    fn mul_div(a: T::Balance, b: T::Balance, c: T::Balance) -> Result<T::Balance, DispatchError> {
+        let result = mul_div_floor(a.saturated_into(), b.saturated_into(), c.saturated_into())
+            .ok_or(Error::<T>::Overflow)?;
+        T::Balance::try_from(result).map_err(|_| Error::<T>::Overflow.into())
+    }
+}
+
+/// `a * b / c` rounded down, or `None` if `c` is zero or the result overflows.
+pub fn mul_div_floor(a: u128, b: u128, c: u128) -> Option<u128> {
+    if c == 0 {
+        return None;
+    }
+    if let Some(product) = a.checked_mul(b) {
+        return Some(product / c);
+    }
+    // Past `u128` the quotient is rounded to the nearest integer, so it may be one too high.
+    let quotient = multiply_by_rational(a, b, c).ok()?;
+    if mul_wide(quotient, c) > mul_wide(a, b) { Some(quotient - 1) } else { Some(quotient) }
+}
+
+/// `sqrt(a * b)` rounded down, without overflowing on the product.
+pub fn sqrt_of_product(a: u128, b: u128) -> u128 {
+    if let Some(product) = a.checked_mul(b) {
+        return product.integer_sqrt();
+    }
+    // The largest root whose square does not exceed the product, found by bisection.
+    let product = mul_wide(a, b);
+    let (mut low, mut high) = (0u128, u128::max_value());
+    while low < high {
+        let middle = low + (high - low) / 2 + 1;
+        if mul_wide(middle, middle) <= product { low = middle } else { high = middle - 1 }
+    }
+    low
+}
+
+// The full 256 bit product of `x` and `y`, as its high and low halves.
+fn mul_wide(x: u128, y: u128) -> (u128, u128) {
+    let (x_high, x_low) = (x >> 64, x & u128::from(u64::MAX));
+    let (y_high, y_low) = (y >> 64, y & u128::from(u64::MAX));
+    let (middle, carry) = (x_high * y_low).overflowing_add(x_low * y_high);
+    let (low, low_carry) = (x_low * y_low).overflowing_add(middle << 64);
+    let high = x_high * y_high + (middle >> 64) + (u128::from(carry) << 64) + u128::from(low_carry);
+    (high, low)
+}
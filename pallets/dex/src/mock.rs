@@ -0,0 +1,96 @@
+use crate as pallet_dex;
+use frame_support::{parameter_types, construct_runtime};
+use sp_core::H256;
+use sp_runtime::{
+	ModuleId, Permill,
+	testing::Header,
+	traits::{BlakeTwo256, IdentityLookup},
+};
+
+type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<TestRuntime>;
+type Block = frame_system::mocking::MockBlock<TestRuntime>;
+
+// Configure a mock runtime to test the pallet.
+construct_runtime!(
+	pub enum TestRuntime where
+		Block = Block,
+		NodeBlock = Block,
+		UncheckedExtrinsic = UncheckedExtrinsic,
+	{
+		System: frame_system::{Module, Call, Config, Storage, Event<T>},
+		Assets: pallet_assets::{Module, Call, Event<T>},
+		Dex: pallet_dex::{Module, Call, Storage, Event<T>},
+	}
+);
+
+parameter_types! {
+	pub const BlockHashCount: u64 = 250;
+	pub const SS58Prefix: u8 = 42;
+}
+
+impl frame_system::Config for TestRuntime {
+	type BaseCallFilter = ();
+	type BlockWeights = ();
+	type BlockLength = ();
+	type Origin = Origin;
+	type Call = Call;
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Hashing = BlakeTwo256;
+	// Wide enough for every pool sub-account to be distinct.
+	type AccountId = u128;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type Event = ();
+	type BlockHashCount = BlockHashCount;
+	type DbWeight = ();
+	type Version = ();
+	type PalletInfo = PalletInfo;
+	type AccountData = ();
+	type OnNewAccount = ();
+	type OnKilledAccount = ();
+	type SystemWeightInfo = ();
+	type SS58Prefix = SS58Prefix;
+}
+
+impl pallet_assets::Config for TestRuntime {
+	type Event = ();
+	type Balance = u128;
+	type AssetId = u32;
+	type TransferGuard = ();
+}
+
+parameter_types! {
+	pub const DexModuleId: ModuleId = ModuleId(*b"stone/dx");
+	pub const ExchangeFee: Permill = Permill::from_percent(1);
+	pub const MinimumLiquidity: u128 = 1_000;
+}
+
+impl pallet_dex::Config for TestRuntime {
+	type Event = ();
+	type ModuleId = DexModuleId;
+	type ExchangeFee = ExchangeFee;
+	type MinimumLiquidity = MinimumLiquidity;
+}
+
+pub const ALICE: u128 = 1;
+pub const BOB: u128 = 2;
+// The assets issued at genesis: ALICE and BOB each hold 1_000_000 of both.
+pub const ASSET_A: u32 = 0;
+pub const ASSET_B: u32 = 1;
+
+// Build genesis storage according to the mock runtime.
+pub fn new_test_ext() -> sp_io::TestExternalities {
+	let storage = frame_system::GenesisConfig::default().build_storage::<TestRuntime>().unwrap();
+	let mut ext: sp_io::TestExternalities = storage.into();
+	ext.execute_with(|| {
+		for _ in 0..2 {
+			let asset_id = Assets::create_asset().unwrap();
+			Assets::mint(asset_id, ALICE, 1_000_000).unwrap();
+			Assets::mint(asset_id, BOB, 1_000_000).unwrap();
+		}
+		System::set_block_number(1);
+	});
+	ext
+}
@@ -0,0 +1,178 @@
+use crate::{mock::*, mul_div_floor, sqrt_of_product, Error};
+use frame_support::{assert_noop, assert_ok};
+
+const LP: u32 = 2;
+
+// Open the A/B pool with ALICE's deposit of 100_000 A and 400_000 B.
+fn setup_pool() {
+	assert_ok!(Dex::create_pool(Origin::signed(ALICE), ASSET_A, ASSET_B));
+	assert_ok!(Dex::add_liquidity(Origin::signed(ALICE), ASSET_A, ASSET_B, 100_000, 400_000, 0));
+}
+
+fn reserves() -> (u128, u128) {
+	let pool = Dex::pool_account(LP);
+	(Assets::balance(ASSET_A, pool), Assets::balance(ASSET_B, pool))
+}
+
+#[test]
+fn create_pool() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(Dex::create_pool(Origin::signed(ALICE), ASSET_A, ASSET_A), Error::<TestRuntime>::IdenticalAssets);
+		assert_noop!(Dex::create_pool(Origin::signed(ALICE), ASSET_A, 7), Error::<TestRuntime>::UnknownAsset);
+
+		assert_ok!(Dex::create_pool(Origin::signed(ALICE), ASSET_B, ASSET_A));
+		assert_eq!(Dex::pool_asset(ASSET_A, ASSET_B), Ok(LP));
+		assert!(Assets::asset_exists(LP));
+		assert_noop!(
+			Dex::create_pool(Origin::signed(BOB), ASSET_A, ASSET_B),
+			Error::<TestRuntime>::PoolAlreadyExists
+		);
+	});
+}
+
+#[test]
+fn first_deposit_locks_minimum_liquidity() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			Dex::add_liquidity(Origin::signed(ALICE), ASSET_A, ASSET_B, 10, 10, 0),
+			Error::<TestRuntime>::PoolNotExist
+		);
+		assert_ok!(Dex::create_pool(Origin::signed(ALICE), ASSET_A, ASSET_B));
+		assert_noop!(
+			Dex::add_liquidity(Origin::signed(ALICE), ASSET_A, ASSET_B, 1_000, 1_000, 0),
+			Error::<TestRuntime>::InsufficientLiquidityMinted
+		);
+
+		assert_ok!(Dex::add_liquidity(Origin::signed(ALICE), ASSET_A, ASSET_B, 100_000, 400_000, 0));
+		assert_eq!(Assets::balance(LP, ALICE), 199_000);
+		assert_eq!(Assets::balance(LP, Dex::pool_account(LP)), 1_000);
+		assert_eq!(reserves(), (100_000, 400_000));
+	});
+}
+
+#[test]
+fn first_deposit_of_large_amounts() {
+	new_test_ext().execute_with(|| {
+		// 18 decimal amounts whose product is beyond u128.
+		let (amount_a, amount_b) = (1_000 * 10u128.pow(18), 4_000 * 10u128.pow(18));
+		assert_ok!(Assets::mint(ASSET_A, ALICE, amount_a));
+		assert_ok!(Assets::mint(ASSET_B, ALICE, amount_b));
+		assert_ok!(Dex::create_pool(Origin::signed(ALICE), ASSET_A, ASSET_B));
+		assert_ok!(Dex::add_liquidity(Origin::signed(ALICE), ASSET_A, ASSET_B, amount_a, amount_b, 0));
+		assert_eq!(Assets::balance(LP, ALICE), 2_000 * 10u128.pow(18) - 1_000);
+		assert_eq!(reserves(), (amount_a, amount_b));
+	});
+}
+
+#[test]
+fn sqrt_of_product_past_u128() {
+	assert_eq!(sqrt_of_product(u128::MAX, u128::MAX), u128::MAX);
+	assert_eq!(sqrt_of_product(u128::MAX, 4), 2u128.pow(64) * 2 - 1);
+	assert_eq!(sqrt_of_product(3, 5), 3);
+}
+
+#[test]
+fn later_deposits_follow_the_pool_price() {
+	new_test_ext().execute_with(|| {
+		setup_pool();
+		assert_noop!(
+			Dex::add_liquidity(Origin::signed(BOB), ASSET_A, ASSET_B, 10_000, 50_000, 20_001),
+			Error::<TestRuntime>::SlippageExceeded
+		);
+
+		// Only as much B as matches the A deposit is taken.
+		assert_ok!(Dex::add_liquidity(Origin::signed(BOB), ASSET_A, ASSET_B, 10_000, 50_000, 20_000));
+		assert_eq!(Assets::balance(LP, BOB), 20_000);
+		assert_eq!(Assets::balance(ASSET_A, BOB), 990_000);
+		assert_eq!(Assets::balance(ASSET_B, BOB), 960_000);
+		assert_eq!(reserves(), (110_000, 440_000));
+	});
+}
+
+#[test]
+fn remove_liquidity() {
+	new_test_ext().execute_with(|| {
+		setup_pool();
+		assert_ok!(Dex::add_liquidity(Origin::signed(BOB), ASSET_A, ASSET_B, 10_000, 40_000, 0));
+
+		assert_noop!(
+			Dex::remove_liquidity(Origin::signed(BOB), ASSET_A, ASSET_B, 20_000, 10_001, 0),
+			Error::<TestRuntime>::SlippageExceeded
+		);
+		assert_noop!(
+			Dex::remove_liquidity(Origin::signed(BOB), ASSET_A, ASSET_B, 20_001, 0, 0),
+			pallet_assets::Error::<TestRuntime>::Underflow
+		);
+		assert_ok!(Dex::remove_liquidity(Origin::signed(BOB), ASSET_B, ASSET_A, 20_000, 40_000, 10_000));
+		assert_eq!(Assets::balance(LP, BOB), 0);
+		assert_eq!(Assets::balance(ASSET_A, BOB), 1_000_000);
+		assert_eq!(Assets::balance(ASSET_B, BOB), 1_000_000);
+		assert_eq!(reserves(), (100_000, 400_000));
+	});
+}
+
+#[test]
+fn swap_exact_in() {
+	new_test_ext().execute_with(|| {
+		setup_pool();
+		assert_eq!(Dex::quote_exact_in(ASSET_A, ASSET_B, 1_000), Ok(3_921));
+		assert_noop!(
+			Dex::swap_exact_in(Origin::signed(BOB), ASSET_A, ASSET_B, 1_000, 3_922),
+			Error::<TestRuntime>::SlippageExceeded
+		);
+		assert_noop!(
+			Dex::swap_exact_in(Origin::signed(BOB), ASSET_A, ASSET_B, 0, 0),
+			Error::<TestRuntime>::ZeroAmount
+		);
+
+		assert_ok!(Dex::swap_exact_in(Origin::signed(BOB), ASSET_A, ASSET_B, 1_000, 3_921));
+		assert_eq!(Assets::balance(ASSET_A, BOB), 999_000);
+		assert_eq!(Assets::balance(ASSET_B, BOB), 1_003_921);
+		assert_eq!(reserves(), (101_000, 396_079));
+	});
+}
+
+#[test]
+fn swap_exact_out() {
+	new_test_ext().execute_with(|| {
+		setup_pool();
+		let amount_in = Dex::quote_exact_out(ASSET_A, ASSET_B, 4_000).unwrap();
+		assert_eq!(amount_in, 1_022);
+		assert_noop!(
+			Dex::swap_exact_out(Origin::signed(BOB), ASSET_A, ASSET_B, 4_000, amount_in - 1),
+			Error::<TestRuntime>::SlippageExceeded
+		);
+		assert_noop!(
+			Dex::swap_exact_out(Origin::signed(BOB), ASSET_A, ASSET_B, 400_000, u128::max_value()),
+			Error::<TestRuntime>::InsufficientLiquidity
+		);
+
+		assert_ok!(Dex::swap_exact_out(Origin::signed(BOB), ASSET_A, ASSET_B, 4_000, amount_in));
+		assert_eq!(Assets::balance(ASSET_A, BOB), 1_000_000 - amount_in);
+		assert_eq!(Assets::balance(ASSET_B, BOB), 1_004_000);
+		// The pool keeps its constant product and the fee.
+		let (reserve_a, reserve_b) = reserves();
+		assert!(u128::from(reserve_a) * u128::from(reserve_b) >= 100_000 * 400_000);
+	});
+}
+
+#[test]
+fn swaps_need_enough_input() {
+	new_test_ext().execute_with(|| {
+		setup_pool();
+		assert_noop!(
+			Dex::swap_exact_in(Origin::signed(3), ASSET_A, ASSET_B, 1_000, 0),
+			pallet_assets::Error::<TestRuntime>::BalanceLow
+		);
+	});
+}
+
+#[test]
+fn mul_div_rounds_down_past_u128() {
+	let (a, b, c) = (136_498_326_688_907_047_595_659_928_587_492_241_909, 800_875, 142_542_696_650_696_974_386_385);
+	// The remainder is more than half of `c`, which `multiply_by_rational` rounds up.
+	assert_eq!(mul_div_floor(a, b, c), Some(766_914_755_758_157_686_024));
+	assert_eq!(mul_div_floor(u128::MAX, u128::MAX, u128::MAX), Some(u128::MAX));
+	assert_eq!(mul_div_floor(7, 3, 2), Some(10));
+	assert_eq!(mul_div_floor(7, 3, 0), None);
+}
@@ -0,0 +1,377 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+//! Prices of `pallet_assets` assets, fed by an authorised set of accounts.
+//!
+//! Every feeder submits its own values. The price of an asset is the median of the values
+//! submitted by current feeders within the last `MaxPriceAge` blocks.
+//!
+//! Every `FeedInterval` blocks, the offchain worker fetches the prices of `FeedAssets` from
+//! `PriceSource` and feeds them with each local `orcl` key belonging to a feeder. The source
+//! must answer with a JSON object mapping symbols to prices, e.g. `{"DOT": 18.25}`.
+
+use codec::{Decode, Encode};
+use frame_support::{
+    debug, decl_error, decl_event, decl_module, decl_storage, ensure,
+    traits::{EnsureOrigin, Get}, weights::Weight,
+};
+use frame_system::{
+    ensure_signed,
+    offchain::{AppCrypto, CreateSignedTransaction, SendSignedTransaction, Signer},
+};
+use lite_json::{JsonValue, NumberValue};
+use sp_core::crypto::KeyTypeId;
+use sp_runtime::{
+    FixedPointNumber, FixedU128, RuntimeAppPublic,
+    offchain::{http, Duration},
+    traits::{IdentifyAccount, Saturating, Zero},
+};
+use sp_std::prelude::*;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+/// The key type of the keys the offchain worker signs its feeds with.
+pub const KEY_TYPE: KeyTypeId = KeyTypeId(*b"orcl");
+
+/// How long the offchain worker waits for the price source, in milliseconds.
+const FETCH_TIMEOUT_MS: u64 = 3_000;
+
+/// The sr25519 keys of price feeders.
+pub mod crypto {
+    use super::KEY_TYPE;
+    use sp_core::sr25519::Signature as Sr25519Signature;
+    use sp_runtime::{
+        MultiSignature, MultiSigner,
+        app_crypto::{app_crypto, sr25519},
+        traits::Verify,
+    };
+
+    app_crypto!(sr25519, KEY_TYPE);
+
+    pub struct OracleAuthId;
+
+    impl frame_system::offchain::AppCrypto<MultiSigner, MultiSignature> for OracleAuthId {
+        type RuntimeAppPublic = Public;
+        type GenericSignature = sp_core::sr25519::Signature;
+        type GenericPublic = sp_core::sr25519::Public;
+    }
+
+    // Lets test runtimes sign with plain sr25519 accounts.
+    impl frame_system::offchain::AppCrypto<<Sr25519Signature as Verify>::Signer, Sr25519Signature> for OracleAuthId {
+        type RuntimeAppPublic = Public;
+        type GenericSignature = sp_core::sr25519::Signature;
+        type GenericPublic = sp_core::sr25519::Public;
+    }
+}
+
+/// The price of one unit of an asset, in a quote unit shared by every asset.
+pub type Price = FixedU128;
+
+/// A price submitted by a feeder, with the block it was submitted at.
+#[derive(Encode, Decode, Default, Clone, PartialEq, Debug)]
+pub struct TimestampedPrice<BlockNumber> {
+    pub price: Price,
+    pub at: BlockNumber,
+}
+
+/// A source of asset prices.
+pub trait PriceProvider<AssetId> {
+    /// The current price of `asset_id`, or `None` if no fresh price is known.
+    fn price(asset_id: AssetId) -> Option<Price>;
+}
+
+pub trait Config: pallet_assets::Config + CreateSignedTransaction<Call<Self>> {
+    type Event: From<Event<Self>> + Into<<Self as frame_system::Config>::Event>;
+    /// The keys the offchain worker signs its feeds with.
+    type AuthorityId: AppCrypto<Self::Public, Self::Signature>;
+    /// The origin allowed to add and remove feeders.
+    type FeederOrigin: EnsureOrigin<Self::Origin>;
+    /// The maximum number of feeders.
+    type MaxFeeders: Get<u32>;
+    /// The number of blocks after which a fed price is no longer taken into account.
+    type MaxPriceAge: Get<Self::BlockNumber>;
+    /// The number of blocks between two fetches of the offchain worker.
+    type FeedInterval: Get<Self::BlockNumber>;
+}
+
+decl_storage! {
+    trait Store for Module<T: Config> as Oracle {
+        /// The accounts allowed to feed prices, in ascending order.
+        Feeders get(fn feeders) build(|config: &GenesisConfig<T>| {
+            let mut feeders = config.feeders.clone();
+            feeders.sort();
+            feeders.dedup();
+            feeders
+        }): Vec<T::AccountId>;
+        /// The latest price each feeder submitted for an asset.
+        RawValues get(fn raw_values):
+            double_map hasher(twox_64_concat) T::AssetId, hasher(blake2_128_concat) T::AccountId => Option<TimestampedPrice<T::BlockNumber>>;
+        /// The URL the offchain worker fetches prices from.
+        PriceSource get(fn price_source) config(): Vec<u8>;
+        /// The assets the offchain worker feeds, with the symbol each is quoted under at the
+        /// price source.
+        FeedAssets get(fn feed_assets) config(): Vec<(T::AssetId, Vec<u8>)>;
+    }
+    add_extra_genesis {
+        config(feeders): Vec<T::AccountId>;
+    }
+}
+
+decl_event!(
+    pub enum Event<T>
+    where
+        AccountId = <T as frame_system::Config>::AccountId,
+        AssetId = <T as pallet_assets::Config>::AssetId,
+    {
+        /// A feeder submitted prices. \[feeder, values\]
+        NewFeedData(AccountId, Vec<(AssetId, Price)>),
+        /// An account was allowed to feed prices. \[feeder\]
+        FeederAdded(AccountId),
+        /// An account is no longer allowed to feed prices. \[feeder\]
+        FeederRemoved(AccountId),
+        /// The offchain worker was pointed at a new price source. \[url, assets\]
+        PriceSourceUpdated(Vec<u8>, Vec<(AssetId, Vec<u8>)>),
+    }
+);
+
+decl_error! {
+    pub enum Error for Module<T: Config> {
+        /// The account is not allowed to feed prices.
+        NotAFeeder,
+        /// The account is already allowed to feed prices.
+        AlreadyAFeeder,
+        /// There are already `MaxFeeders` feeders.
+        TooManyFeeders,
+    }
+}
+
+decl_module! {
+    pub struct Module<T: Config> for enum Call where origin: T::Origin {
+        type Error = Error<T>;
+
+        fn deposit_event() = default;
+
+        /// The maximum number of feeders.
+        const MaxFeeders: u32 = T::MaxFeeders::get();
+
+        /// The number of blocks after which a fed price is no longer taken into account.
+        const MaxPriceAge: T::BlockNumber = T::MaxPriceAge::get();
+
+        /// The number of blocks between two fetches of the offchain worker.
+        const FeedInterval: T::BlockNumber = T::FeedInterval::get();
+
+        /// Submit the caller's prices for some assets, replacing the ones it fed before.
+        #[weight = 10_000 + T::DbWeight::get().reads_writes(1, values.len() as Weight)]
+        pub fn feed_values(origin, values: Vec<(T::AssetId, Price)>) {
+            let who = ensure_signed(origin)?;
+            ensure!(Self::is_feeder(&who), Error::<T>::NotAFeeder);
+
+            Self::do_feed_values(who, values);
+        }
+
+        /// Allow `who` to feed prices.
+        #[weight = 10_000 + T::DbWeight::get().reads_writes(1, 1)]
+        pub fn add_feeder(origin, who: T::AccountId) {
+            T::FeederOrigin::ensure_origin(origin)?;
+            let mut feeders = Self::feeders();
+            ensure!(feeders.len() < T::MaxFeeders::get() as usize, Error::<T>::TooManyFeeders);
+            let position = feeders.binary_search(&who).err().ok_or(Error::<T>::AlreadyAFeeder)?;
+
+            feeders.insert(position, who.clone());
+            <Feeders<T>>::put(feeders);
+            Self::deposit_event(RawEvent::FeederAdded(who));
+        }
+
+        /// Stop `who` from feeding prices. The prices it fed are ignored from now on.
+        #[weight = 10_000 + T::DbWeight::get().reads_writes(1, 1)]
+        pub fn remove_feeder(origin, who: T::AccountId) {
+            T::FeederOrigin::ensure_origin(origin)?;
+            let mut feeders = Self::feeders();
+            let position = feeders.binary_search(&who).map_err(|_| Error::<T>::NotAFeeder)?;
+
+            feeders.remove(position);
+            <Feeders<T>>::put(feeders);
+            Self::deposit_event(RawEvent::FeederRemoved(who));
+        }
+
+        /// Make the offchain worker fetch the prices of `assets` from `url`, each under the
+        /// given symbol.
+        #[weight = 10_000 + T::DbWeight::get().writes(2)]
+        pub fn set_price_source(origin, url: Vec<u8>, assets: Vec<(T::AssetId, Vec<u8>)>) {
+            T::FeederOrigin::ensure_origin(origin)?;
+
+            <PriceSource>::put(&url);
+            <FeedAssets<T>>::put(&assets);
+            Self::deposit_event(RawEvent::PriceSourceUpdated(url, assets));
+        }
+
+        fn offchain_worker(block_number: T::BlockNumber) {
+            let interval = T::FeedInterval::get().max(1u32.into());
+            if !(block_number % interval).is_zero() {
+                return;
+            }
+            if let Err(e) = Self::fetch_and_feed() {
+                debug::warn!("Oracle offchain worker failed to feed prices: {}", e);
+            }
+        }
+    }
+}
+
+impl<T: Config> Module<T> {
+    /// Whether `who` may feed prices.
+    pub fn is_feeder(who: &T::AccountId) -> bool {
+        Self::feeders().binary_search(who).is_ok()
+    }
+
+    /// The median of the prices fed for `asset_id` by current feeders within the last
+    /// `MaxPriceAge` blocks. With an even number of prices the upper middle one is taken.
+    pub fn aggregated_price(asset_id: T::AssetId) -> Option<Price> {
+        let now = <frame_system::Module<T>>::block_number();
+        let mut prices: Vec<Price> = Self::feeders()
+            .iter()
+            .filter_map(|feeder| Self::raw_values(asset_id, feeder))
+            .filter(|value| now.saturating_sub(value.at) <= T::MaxPriceAge::get())
+            .map(|value| value.price)
+            .collect();
+        if prices.is_empty() {
+            return None;
+        }
+        prices.sort();
+        Some(prices[prices.len() / 2])
+    }
+
+    /// Fetch the prices of `FeedAssets` and feed them with every local key of a feeder.
+    pub fn fetch_and_feed() -> Result<(), &'static str> {
+        let assets = Self::feed_assets();
+        if assets.is_empty() {
+            return Ok(());
+        }
+        let keys = Self::local_feeder_keys();
+        if keys.is_empty() {
+            return Ok(());
+        }
+
+        let body = Self::fetch(&Self::price_source()).map_err(|_| "the price source could not be reached")?;
+        let prices = Self::parse_prices(&body, &assets).ok_or("the price source answered with malformed JSON")?;
+        if prices.is_empty() {
+            return Err("the price source quoted none of the feed assets");
+        }
+
+        let results = Signer::<T, T::AuthorityId>::all_accounts()
+            .with_filter(keys)
+            .send_signed_transaction(|_account| Call::feed_values(prices.clone()));
+        for (account, result) in results.iter() {
+            if result.is_err() {
+                debug::warn!("Oracle feed by {:?} could not be submitted", account.id);
+            }
+        }
+        Ok(())
+    }
+
+    // The local `KEY_TYPE` keys whose account is allowed to feed.
+    fn local_feeder_keys() -> Vec<T::Public> {
+        let feeders = Self::feeders();
+        <T::AuthorityId as AppCrypto<T::Public, T::Signature>>::RuntimeAppPublic::all()
+            .into_iter()
+            .map(|key| {
+                let generic: <T::AuthorityId as AppCrypto<T::Public, T::Signature>>::GenericPublic = key.into();
+                generic.into()
+            })
+            .filter(|public: &T::Public| feeders.binary_search(&public.clone().into_account()).is_ok())
+            .collect()
+    }
+
+    fn fetch(url: &[u8]) -> Result<Vec<u8>, http::Error> {
+        let url = sp_std::str::from_utf8(url).map_err(|_| http::Error::Unknown)?;
+        let deadline = sp_io::offchain::timestamp().add(Duration::from_millis(FETCH_TIMEOUT_MS));
+        let pending = http::Request::get(url)
+            .deadline(deadline)
+            .send()
+            .map_err(|_| http::Error::IoError)?;
+        let response = pending.try_wait(deadline).map_err(|_| http::Error::DeadlineReached)??;
+        if response.code != 200 {
+            return Err(http::Error::Unknown);
+        }
+        Ok(response.body().collect())
+    }
+
+    /// The prices of `assets` quoted in `body`, a JSON object mapping symbols to prices.
+    /// Symbols missing from the object or not quoted as a non-negative number are skipped.
+    pub fn parse_prices(body: &[u8], assets: &[(T::AssetId, Vec<u8>)]) -> Option<Vec<(T::AssetId, Price)>> {
+        let body = sp_std::str::from_utf8(body).ok()?;
+        let fields = match lite_json::parse_json(body) {
+            Ok(JsonValue::Object(fields)) => fields,
+            _ => return None,
+        };
+        let prices = assets
+            .iter()
+            .filter_map(|(asset_id, symbol)| {
+                let (_, value) = fields
+                    .iter()
+                    .find(|(key, _)| key.iter().copied().eq(symbol.iter().map(|byte| *byte as char)))?;
+                match value {
+                    JsonValue::Number(_) if Self::is_quoted_negative(body, symbol) => None,
+                    JsonValue::Number(number) => Self::number_to_price(number).map(|price| (*asset_id, price)),
+                    _ => None,
+                }
+            })
+            .collect();
+        Some(prices)
+    }
+
+    // Whether `symbol` is quoted with a negative number in `body`. `NumberValue` only keeps
+    // the sign in its integer part, so it is lost for values between -1 and 0.
+    fn is_quoted_negative(body: &str, symbol: &[u8]) -> bool {
+        let body = body.as_bytes();
+        (0..body.len()).any(|start| {
+            let key_end = start + symbol.len() + 1;
+            body[start] == b'"'
+                && body[start + 1..].starts_with(symbol)
+                && body.get(key_end) == Some(&b'"')
+                && body[key_end + 1..]
+                    .iter()
+                    .filter(|byte| !byte.is_ascii_whitespace())
+                    .take(2)
+                    .eq([b':', b'-'].iter())
+        })
+    }
+
+    fn number_to_price(number: &NumberValue) -> Option<Price> {
+        if number.integer < 0 || number.exponent != 0 {
+            return None;
+        }
+        // Digits beyond the precision of `Price` are dropped.
+        let precision = 18u32;
+        let fraction = if number.fraction_length > precision {
+            number.fraction / 10u64.checked_pow(number.fraction_length - precision)?
+        } else {
+            number.fraction
+        };
+        let fraction_scale = 10u128.checked_pow(precision.saturating_sub(number.fraction_length))?;
+        let inner = (number.integer as u128)
+            .checked_mul(Price::accuracy())?
+            .checked_add((fraction as u128).checked_mul(fraction_scale)?)?;
+        Some(Price::from_inner(inner))
+    }
+
+    fn do_feed_values(who: T::AccountId, values: Vec<(T::AssetId, Price)>) {
+        let now = <frame_system::Module<T>>::block_number();
+        for (asset_id, price) in values.iter() {
+            <RawValues<T>>::insert(asset_id, &who, TimestampedPrice { price: *price, at: now });
+        }
+        Self::deposit_event(RawEvent::NewFeedData(who, values));
+    }
+}
+
+impl<T: Config> PriceProvider<T::AssetId> for Module<T> {
+    fn price(asset_id: T::AssetId) -> Option<Price> {
+        Self::aggregated_price(asset_id)
+    }
+}
+
+impl<T: Config> sp_runtime::BoundToRuntimeAppPublic for Module<T> {
+    type Public = crypto::Public;
+}
@@ -0,0 +1,130 @@
+use crate as pallet_oracle;
+use frame_support::{parameter_types, construct_runtime};
+use frame_system::EnsureRoot;
+use sp_core::{H256, sr25519::Signature};
+use sp_runtime::{
+	BuildStorage,
+	testing::{Header, TestXt},
+	traits::{BlakeTwo256, Extrinsic as ExtrinsicT, IdentifyAccount, IdentityLookup, Verify},
+};
+
+pub type Extrinsic = TestXt<Call, ()>;
+// Feeders sign with sr25519 keys, so accounts are sr25519 public keys.
+pub type AccountId = <<Signature as Verify>::Signer as IdentifyAccount>::AccountId;
+
+type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<TestRuntime>;
+type Block = frame_system::mocking::MockBlock<TestRuntime>;
+
+// Configure a mock runtime to test the pallet.
+construct_runtime!(
+	pub enum TestRuntime where
+		Block = Block,
+		NodeBlock = Block,
+		UncheckedExtrinsic = UncheckedExtrinsic,
+	{
+		System: frame_system::{Module, Call, Config, Storage, Event<T>},
+		Assets: pallet_assets::{Module, Call, Event<T>},
+		Oracle: pallet_oracle::{Module, Call, Storage, Config<T>, Event<T>},
+	}
+);
+
+parameter_types! {
+	pub const BlockHashCount: u64 = 250;
+	pub const SS58Prefix: u8 = 42;
+}
+
+impl frame_system::Config for TestRuntime {
+	type BaseCallFilter = ();
+	type BlockWeights = ();
+	type BlockLength = ();
+	type Origin = Origin;
+	type Call = Call;
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Hashing = BlakeTwo256;
+	type AccountId = AccountId;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type Event = ();
+	type BlockHashCount = BlockHashCount;
+	type DbWeight = ();
+	type Version = ();
+	type PalletInfo = PalletInfo;
+	type AccountData = ();
+	type OnNewAccount = ();
+	type OnKilledAccount = ();
+	type SystemWeightInfo = ();
+	type SS58Prefix = SS58Prefix;
+}
+
+impl pallet_assets::Config for TestRuntime {
+	type Event = ();
+	type Balance = u64;
+	type AssetId = u32;
+	type TransferGuard = ();
+}
+
+impl frame_system::offchain::SigningTypes for TestRuntime {
+	type Public = <Signature as Verify>::Signer;
+	type Signature = Signature;
+}
+
+impl<LocalCall> frame_system::offchain::SendTransactionTypes<LocalCall> for TestRuntime
+where
+	Call: From<LocalCall>,
+{
+	type OverarchingCall = Call;
+	type Extrinsic = Extrinsic;
+}
+
+impl<LocalCall> frame_system::offchain::CreateSignedTransaction<LocalCall> for TestRuntime
+where
+	Call: From<LocalCall>,
+{
+	fn create_transaction<C: frame_system::offchain::AppCrypto<Self::Public, Self::Signature>>(
+		call: Call,
+		_public: <Signature as Verify>::Signer,
+		_account: AccountId,
+		nonce: u64,
+	) -> Option<(Call, <Extrinsic as ExtrinsicT>::SignaturePayload)> {
+		Some((call, (nonce, ())))
+	}
+}
+
+parameter_types! {
+	pub const MaxFeeders: u32 = 4;
+	pub const MaxPriceAge: u64 = 10;
+	pub const FeedInterval: u64 = 5;
+}
+
+impl pallet_oracle::Config for TestRuntime {
+	type Event = ();
+	type AuthorityId = pallet_oracle::crypto::OracleAuthId;
+	type FeederOrigin = EnsureRoot<AccountId>;
+	type MaxFeeders = MaxFeeders;
+	type MaxPriceAge = MaxPriceAge;
+	type FeedInterval = FeedInterval;
+}
+
+pub const PRICE_SOURCE: &str = "https://prices.example/v1/latest";
+
+// A distinct account for every `n`, ordered like `n`.
+pub fn account(n: u8) -> AccountId {
+	sp_core::sr25519::Public::from_raw([n; 32])
+}
+
+// Build genesis storage with accounts 1, 2 and 3 as feeders.
+pub fn new_test_ext() -> sp_io::TestExternalities {
+	let config = GenesisConfig {
+		frame_system: Some(Default::default()),
+		pallet_oracle: Some(pallet_oracle::GenesisConfig {
+			feeders: vec![account(3), account(1), account(2)],
+			price_source: PRICE_SOURCE.as_bytes().to_vec(),
+			feed_assets: vec![(7, b"DOT".to_vec()), (8, b"KSM".to_vec())],
+		}),
+	};
+	let mut ext: sp_io::TestExternalities = config.build_storage().unwrap().into();
+	ext.execute_with(|| System::set_block_number(1));
+	ext
+}
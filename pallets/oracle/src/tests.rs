@@ -0,0 +1,193 @@
+use crate::{mock::*, Error, Price, PriceProvider};
+use codec::Decode;
+use frame_support::{assert_noop, assert_ok, traits::OffchainWorker};
+use parking_lot::RwLock;
+use sp_core::offchain::{
+	testing::{self, OffchainState, PoolState, TestOffchainExt, TestTransactionPoolExt},
+	OffchainExt, TransactionPoolExt,
+};
+use sp_keystore::{testing::KeyStore, KeystoreExt, SyncCryptoStore};
+use sp_runtime::{DispatchError, FixedPointNumber};
+use std::sync::Arc;
+
+const ASSET: u32 = 7;
+
+fn price(n: u128) -> Price {
+	Price::saturating_from_integer(n)
+}
+
+#[test]
+fn genesis_feeders_are_sorted() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(Oracle::feeders(), vec![account(1), account(2), account(3)]);
+	});
+}
+
+#[test]
+fn only_feeders_can_feed() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			Oracle::feed_values(Origin::signed(account(4)), vec![(ASSET, price(1))]),
+			Error::<TestRuntime>::NotAFeeder
+		);
+		assert_ok!(Oracle::feed_values(Origin::signed(account(1)), vec![(ASSET, price(1))]));
+		assert_eq!(Oracle::raw_values(ASSET, account(1)).unwrap().price, price(1));
+		assert_eq!(Oracle::price(ASSET), Some(price(1)));
+	});
+}
+
+#[test]
+fn price_is_the_median_of_the_feeders() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(Oracle::price(ASSET), None);
+		assert_ok!(Oracle::feed_values(Origin::signed(account(1)), vec![(ASSET, price(10))]));
+		assert_ok!(Oracle::feed_values(Origin::signed(account(2)), vec![(ASSET, price(1_000))]));
+		// With two prices the upper one is taken.
+		assert_eq!(Oracle::price(ASSET), Some(price(1_000)));
+
+		assert_ok!(Oracle::feed_values(Origin::signed(account(3)), vec![(ASSET, price(12)), (8, price(3))]));
+		assert_eq!(Oracle::price(ASSET), Some(price(12)));
+		assert_eq!(Oracle::price(8), Some(price(3)));
+
+		// A later feed replaces the feeder's previous price.
+		assert_ok!(Oracle::feed_values(Origin::signed(account(2)), vec![(ASSET, price(11))]));
+		assert_eq!(Oracle::price(ASSET), Some(price(11)));
+	});
+}
+
+#[test]
+fn stale_prices_are_ignored() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Oracle::feed_values(Origin::signed(account(1)), vec![(ASSET, price(10))]));
+		System::set_block_number(6);
+		assert_ok!(Oracle::feed_values(Origin::signed(account(2)), vec![(ASSET, price(20))]));
+		assert_eq!(Oracle::price(ASSET), Some(price(20)));
+
+		// The first price was fed at block 1 and is too old from block 12 on.
+		System::set_block_number(11);
+		assert_ok!(Oracle::feed_values(Origin::signed(account(3)), vec![(ASSET, price(30))]));
+		assert_eq!(Oracle::price(ASSET), Some(price(20)));
+		System::set_block_number(12);
+		assert_eq!(Oracle::price(ASSET), Some(price(30)));
+
+		System::set_block_number(22);
+		assert_eq!(Oracle::price(ASSET), None);
+	});
+}
+
+#[test]
+fn add_and_remove_feeders() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(Oracle::add_feeder(Origin::signed(account(1)), account(5)), DispatchError::BadOrigin);
+		assert_noop!(Oracle::add_feeder(Origin::root(), account(2)), Error::<TestRuntime>::AlreadyAFeeder);
+		assert_ok!(Oracle::add_feeder(Origin::root(), account(0)));
+		assert_eq!(Oracle::feeders(), vec![account(0), account(1), account(2), account(3)]);
+		assert_noop!(Oracle::add_feeder(Origin::root(), account(5)), Error::<TestRuntime>::TooManyFeeders);
+
+		// The prices of a removed feeder no longer count.
+		assert_ok!(Oracle::feed_values(Origin::signed(account(2)), vec![(ASSET, price(20))]));
+		assert_ok!(Oracle::remove_feeder(Origin::root(), account(2)));
+		assert_eq!(Oracle::feeders(), vec![account(0), account(1), account(3)]);
+		assert_eq!(Oracle::price(ASSET), None);
+		assert_noop!(Oracle::remove_feeder(Origin::root(), account(2)), Error::<TestRuntime>::NotAFeeder);
+		assert_noop!(
+			Oracle::feed_values(Origin::signed(account(2)), vec![(ASSET, price(20))]),
+			Error::<TestRuntime>::NotAFeeder
+		);
+	});
+}
+
+// Test externalities with an offchain worker environment and an empty keystore.
+fn new_offchain_ext() -> (
+	sp_io::TestExternalities,
+	Arc<RwLock<OffchainState>>,
+	Arc<RwLock<PoolState>>,
+	Arc<KeyStore>,
+) {
+	let (offchain, offchain_state) = TestOffchainExt::new();
+	let (pool, pool_state) = TestTransactionPoolExt::new();
+	let keystore = Arc::new(KeyStore::new());
+	let mut ext = new_test_ext();
+	ext.register_extension(OffchainExt::new(offchain));
+	ext.register_extension(TransactionPoolExt::new(pool));
+	ext.register_extension(KeystoreExt(keystore.clone()));
+	(ext, offchain_state, pool_state, keystore)
+}
+
+fn expect_price_request(state: &Arc<RwLock<OffchainState>>, body: &[u8]) {
+	state.write().expect_request(testing::PendingRequest {
+		method: "GET".into(),
+		uri: PRICE_SOURCE.into(),
+		response: Some(body.to_vec()),
+		sent: true,
+		..Default::default()
+	});
+}
+
+#[test]
+fn offchain_worker_feeds_fetched_prices() {
+	let (mut ext, offchain_state, pool_state, keystore) = new_offchain_ext();
+	let public = SyncCryptoStore::sr25519_generate_new(&*keystore, crate::KEY_TYPE, Some("//Alice")).unwrap();
+	expect_price_request(&offchain_state, br#"{"DOT":18.25,"KSM":250,"BTC":60000}"#);
+
+	ext.execute_with(|| {
+		assert_ok!(Oracle::add_feeder(Origin::root(), public));
+		Oracle::offchain_worker(5);
+
+		let tx = pool_state.write().transactions.pop().unwrap();
+		assert!(pool_state.read().transactions.is_empty());
+		let tx = Extrinsic::decode(&mut &*tx).unwrap();
+		assert_eq!(tx.signature.unwrap().0, 0);
+		assert_eq!(
+			tx.call,
+			Call::Oracle(crate::Call::feed_values(vec![
+				(7, Price::from_inner(18_250_000_000_000_000_000)),
+				(8, price(250)),
+			]))
+		);
+	});
+}
+
+#[test]
+fn offchain_worker_only_runs_every_feed_interval() {
+	let (mut ext, _offchain_state, pool_state, keystore) = new_offchain_ext();
+	let public = SyncCryptoStore::sr25519_generate_new(&*keystore, crate::KEY_TYPE, Some("//Alice")).unwrap();
+
+	ext.execute_with(|| {
+		assert_ok!(Oracle::add_feeder(Origin::root(), public));
+		// No request is expected, so fetching here would panic.
+		Oracle::offchain_worker(4);
+		assert!(pool_state.read().transactions.is_empty());
+	});
+}
+
+#[test]
+fn offchain_worker_needs_a_feeder_key() {
+	let (mut ext, _offchain_state, pool_state, keystore) = new_offchain_ext();
+	SyncCryptoStore::sr25519_generate_new(&*keystore, crate::KEY_TYPE, Some("//Alice")).unwrap();
+
+	ext.execute_with(|| {
+		// The local key is not a feeder, so nothing is fetched nor fed.
+		assert_ok!(Oracle::fetch_and_feed());
+		assert!(pool_state.read().transactions.is_empty());
+	});
+}
+
+#[test]
+fn parsing_prices() {
+	new_test_ext().execute_with(|| {
+		let assets = Oracle::feed_assets();
+		assert_eq!(Oracle::parse_prices(b"[18.25]", &assets), None);
+		assert_eq!(Oracle::parse_prices(b"{\"DOT\":", &assets), None);
+		assert_eq!(
+			Oracle::parse_prices(br#"{"DOT":"18","KSM":0.5,"ETH":1}"#, &assets),
+			Some(vec![(8, Price::saturating_from_rational(1, 2))])
+		);
+		assert_eq!(Oracle::parse_prices(br#"{"DOT":-1,"KSM":1e3}"#, &assets), Some(vec![]));
+		// The sign of a number between -1 and 0 is only in its text.
+		assert_eq!(
+			Oracle::parse_prices(br#"{"DOT": -0.5,"KSM":0.5}"#, &assets),
+			Some(vec![(8, Price::saturating_from_rational(1, 2))])
+		);
+	});
+}
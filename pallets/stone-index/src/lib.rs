@@ -1,13 +1,25 @@
 #![cfg_attr(not(feature = "std"), no_std)]
+// `decl_module!` does a lot of recursion and requires us to increase the limit to 256.
+#![recursion_limit = "256"]
 
 use frame_support::codec::{Decode, Encode};
 use frame_support::{
-    Parameter, decl_error, decl_event, decl_module, decl_storage, ensure, traits::Get,
+    Parameter, decl_error, decl_event, decl_module, decl_storage, ensure, transactional,
+    dispatch::DispatchResult, storage::IterableStorageDoubleMap,
+    traits::{Currency, EnsureOrigin, Get, ReservableCurrency}, weights::Weight,
 };
 use frame_system::ensure_signed;
-use sp_runtime::traits::{Zero, StaticLookup, AtLeast32BitUnsigned, MaybeSerializeDeserialize};
+use pallet_oracle::{Price, PriceProvider};
+use sp_runtime::{
+    DispatchError, FixedPointNumber, FixedU128, ModuleId, Perbill, Permill, PerThing, SaturatedConversion,
+    helpers_128bit::multiply_by_rational,
+    traits::{
+        One, Zero, StaticLookup, AtLeast32BitUnsigned, MaybeSerializeDeserialize, AccountIdConversion,
+        CheckedAdd, CheckedSub, CheckedMul, CheckedDiv, Saturating,
+    },
+};
 
-use sp_std::prelude::*;
+use sp_std::{convert::TryInto, prelude::*};
 
 #[cfg(test)]
 mod mock;
@@ -15,10 +27,67 @@ mod mock;
 #[cfg(test)]
 mod tests;
 
-#[derive(Encode, Decode, Default, Clone, PartialEq, Debug)]
+#[derive(Encode, Decode, Default, Clone, PartialEq, Eq, Debug)]
 pub struct StoneIndexComponent<AssetId> {
     pub asset_id: AssetId,
-    pub weight: u32,
+    /// The amount of the component backing one whole unit of the index, in the smallest
+    /// units of the component. It may be fractional.
+    pub weight: FixedU128,
+    /// The share of the value of the index held in this component. Only used by
+    /// value-weighted indexes, which derive `weight` from it.
+    pub target: Permill,
+}
+
+/// How the composition of an index is specified.
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CompositionMode {
+    /// Each unit holds `weight` of every component.
+    Quantity,
+    /// Each component makes up `target` of the value of a unit. The quantities are derived
+    /// from oracle prices when the index is created and whenever its composition is updated.
+    ValueWeighted,
+}
+
+impl Default for CompositionMode {
+    fn default() -> Self {
+        CompositionMode::Quantity
+    }
+}
+
+/// Which operations an index currently allows.
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum IndexStatus {
+    /// Units can be bought, sold and transferred.
+    Active,
+    /// Units cannot be bought.
+    MintPaused,
+    /// Units cannot be sold.
+    RedeemPaused,
+    /// Units cannot be bought, sold or transferred.
+    Frozen,
+    /// The index is being wound down: units can only be transferred or settled through
+    /// `claim_settlement`. This is final.
+    Retired,
+}
+
+impl Default for IndexStatus {
+    fn default() -> Self {
+        IndexStatus::Active
+    }
+}
+
+impl IndexStatus {
+    pub fn allows_mint(&self) -> bool {
+        matches!(self, IndexStatus::Active | IndexStatus::RedeemPaused)
+    }
+
+    pub fn allows_redeem(&self) -> bool {
+        matches!(self, IndexStatus::Active | IndexStatus::MintPaused)
+    }
+
+    pub fn allows_transfer(&self) -> bool {
+        *self != IndexStatus::Frozen
+    }
 }
 
 #[derive(Encode, Decode, Default, Clone, PartialEq, Debug)]
@@ -27,11 +96,135 @@ pub struct StoneIndex<IndexId, AssetId, AccountId> {
     pub name: Vec<u8>,
     pub components: Vec<StoneIndexComponent<AssetId>>,
     pub owner: AccountId,
+    /// An account which may update and pause the index on behalf of the owner.
+    pub operator: Option<AccountId>,
+    /// The asset in `pallet_assets` whose units are the units of the index.
+    pub asset_id: AssetId,
+    /// How the components of the index are specified.
+    pub mode: CompositionMode,
+    /// The number of decimals of the index: one whole unit is `10^decimals` of its smallest
+    /// units, the ones balances are counted in.
+    pub decimals: u8,
+    /// Which operations the index currently allows.
+    pub status: IndexStatus,
+    /// The share of bought units paid as a fee.
+    pub mint_fee: Permill,
+    /// The share of sold units paid as a fee.
+    pub redeem_fee: Permill,
+    /// The share of the supply minted to the fee recipient over a year.
+    pub streaming_fee: Perbill,
+}
+
+/// How a conversion from index units into component amounts is rounded.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Rounding {
+    /// Round up, for amounts paid into the vault.
+    Up,
+    /// Round down, for amounts paid out of the vault.
+    Down,
 }
 
+/// The most decimals an index may have, the precision of its component quantities.
+pub const MAX_DECIMALS: u8 = 18;
+
+/// An exchange single-asset trades of index units are routed through.
+pub trait Swap<AccountId, AssetId, Balance> {
+    /// Swap at most `max_amount_in` of `asset_in` held by `who` for exactly `amount_out` of
+    /// `asset_out`, returning the amount paid.
+    fn swap_exact_out(
+        who: &AccountId,
+        asset_in: AssetId,
+        asset_out: AssetId,
+        amount_out: Balance,
+        max_amount_in: Balance,
+    ) -> Result<Balance, DispatchError>;
+
+    /// Swap exactly `amount_in` of `asset_in` held by `who` for at least `min_amount_out` of
+    /// `asset_out`, returning the amount received.
+    fn swap_exact_in(
+        who: &AccountId,
+        asset_in: AssetId,
+        asset_out: AssetId,
+        amount_in: Balance,
+        min_amount_out: Balance,
+    ) -> Result<Balance, DispatchError>;
+}
+
+/// A composition change waiting for its timelock to expire.
+#[derive(Encode, Decode, Default, Clone, PartialEq, Debug)]
+pub struct PendingUpdate<AssetId, BlockNumber> {
+    pub name: Vec<u8>,
+    pub components: Vec<StoneIndexComponent<AssetId>>,
+    pub enact_at: BlockNumber,
+}
+
+/// Limits on how many units of an index may be bought, each unset when unlimited.
+#[derive(Encode, Decode, Default, Clone, PartialEq, Debug)]
+pub struct MintLimits<Balance> {
+    /// The largest supply buying may raise the index to.
+    pub max_supply: Option<Balance>,
+    /// The most units a single account may buy in total.
+    pub per_account: Option<Balance>,
+    /// The most units that may be bought within a single block.
+    pub per_block: Option<Balance>,
+}
+
+/// The human-readable description of an index.
+#[derive(Encode, Decode, Default, Clone, PartialEq, Debug)]
+pub struct StoneIndexMetadata<Balance, AccountId> {
+    /// The ticker of the index, e.g. "iDOT".
+    pub symbol: Vec<u8>,
+    /// The decimals of the index.
+    pub decimals: u8,
+    pub description: Vec<u8>,
+    pub url: Option<Vec<u8>>,
+    /// The account the deposit is reserved from.
+    pub depositor: AccountId,
+    /// The deposit reserved for storing the metadata.
+    pub deposit: Balance,
+}
+
+type DepositBalanceOf<T> = <<T as Config>::Currency as Currency<<T as frame_system::Config>::AccountId>>::Balance;
+
 pub trait Config: pallet_assets::Config {
     type Event: From<Event<Self>> + Into<<Self as frame_system::Config>::Event>;
     type IndexId: Parameter + AtLeast32BitUnsigned + Default + Copy + MaybeSerializeDeserialize;
+    /// The pallet id, used for deriving the vault account of each index.
+    type ModuleId: Get<ModuleId>;
+    /// The maximum number of components an index may hold.
+    type MaxComponents: Get<u32>;
+    /// The maximum length of an index name, in bytes.
+    type MaxNameLength: Get<u32>;
+    /// The number of blocks holders are given to exit before a proposed update is enacted.
+    type UpdateDelay: Get<Self::BlockNumber>;
+    /// The origin allowed to create indexes; it becomes the owner of the new index.
+    type CreateOrigin: EnsureOrigin<Self::Origin, Success = Self::AccountId>;
+    /// The origin allowed to call the `force_*` family of emergency calls.
+    type AdminOrigin: EnsureOrigin<Self::Origin>;
+    /// The account receiving mint and redeem fees; the index owner when `None`.
+    type FeeRecipient: Get<Option<Self::AccountId>>;
+    /// The highest mint or redeem fee an owner may set.
+    type MaxFee: Get<Permill>;
+    /// The number of blocks over which the annual streaming fee accrues.
+    type BlocksPerYear: Get<Self::BlockNumber>;
+    /// The highest annual streaming fee an owner may set.
+    type MaxStreamingFee: Get<Perbill>;
+    /// The exchange used to buy and sell components for a single asset.
+    type Swap: Swap<Self::AccountId, Self::AssetId, Self::Balance>;
+    /// The source of the component prices the net asset value is computed from.
+    type PriceProvider: PriceProvider<Self::AssetId>;
+    /// The value of one unit of a value-weighted index when it is created.
+    type InitialUnitValue: Get<Price>;
+    /// The currency metadata deposits are reserved in.
+    type Currency: ReservableCurrency<Self::AccountId>;
+    /// The deposit reserved for storing the metadata of an index.
+    type MetadataDepositBase: Get<DepositBalanceOf<Self>>;
+    /// The additional deposit reserved per byte of metadata.
+    type MetadataDepositPerByte: Get<DepositBalanceOf<Self>>;
+    /// The maximum length of an index symbol, in bytes.
+    type MaxSymbolLength: Get<u32>;
+    /// The maximum length of an index description or URL, in bytes.
+    type MaxDescriptionLength: Get<u32>;
 }
 
 // The pallet's runtime storage items.
@@ -41,8 +234,55 @@ decl_storage! {
     // This name may be updated, but each pallet in the runtime must use a unique name.
     // ---------------------------------vvvvvvvvvvvvvv
     trait Store for Module<T: Config> as StoneIndexPallet {
-        Indexes get(fn indexes) config(): map hasher(blake2_128_concat) T::IndexId => StoneIndex<T::IndexId, T::AssetId, T::AccountId>;
-        IndexBalances get(fn index_balances): map hasher(blake2_128_concat) (T::IndexId, T::AccountId) => T::Balance;
+        Indexes get(fn indexes): map hasher(blake2_128_concat) T::IndexId => StoneIndex<T::IndexId, T::AssetId, T::AccountId>;
+        /// The index whose units each asset represents.
+        AssetIndex get(fn asset_index): map hasher(blake2_128_concat) T::AssetId => Option<T::IndexId>;
+        /// The identifier given to the next index created through `add_index`.
+        NextIndexId get(fn next_index_id): T::IndexId;
+        /// Updates proposed by index owners which have not been enacted yet.
+        PendingUpdates get(fn pending_updates): map hasher(blake2_128_concat) T::IndexId => Option<PendingUpdate<T::AssetId, T::BlockNumber>>;
+        /// The indexes whose pending update falls due at a given block.
+        UpdateSchedule get(fn update_schedule): map hasher(twox_64_concat) T::BlockNumber => Vec<T::IndexId>;
+        /// The block up to which the streaming fee of each index has been charged.
+        LastFeeAccrual get(fn last_fee_accrual): map hasher(blake2_128_concat) T::IndexId => T::BlockNumber;
+        /// The share of the component weights backing one unit of each index. Unset means one;
+        /// it shrinks as streaming fee units dilute the supply.
+        UnitMultiplier: map hasher(blake2_128_concat) T::IndexId => Option<FixedU128>;
+        /// The symbol, description and URL of each index.
+        IndexMetadata get(fn index_metadata):
+            map hasher(blake2_128_concat) T::IndexId => Option<StoneIndexMetadata<DepositBalanceOf<T>, T::AccountId>>;
+        /// The supply cap and mint limits of each index.
+        IndexMintLimits get(fn mint_limits): map hasher(blake2_128_concat) T::IndexId => MintLimits<T::Balance>;
+        /// The units of each index bought so far by an account, counted against `per_account`.
+        MintedBy get(fn minted_by):
+            double_map hasher(blake2_128_concat) T::IndexId, hasher(blake2_128_concat) T::AccountId => T::Balance;
+        /// The units of each index bought in the block it was last bought in.
+        MintedInBlock get(fn minted_in_block):
+            map hasher(blake2_128_concat) T::IndexId => (T::BlockNumber, T::Balance);
+        /// The account each index was offered to by its owner, until it accepts.
+        PendingOwners get(fn pending_owner): map hasher(blake2_128_concat) T::IndexId => Option<T::AccountId>;
+        /// The number of units of an index a spender may move on behalf of a holder, keyed by
+        /// the index and the holder and spender.
+        Allowances get(fn allowance):
+            double_map hasher(blake2_128_concat) T::IndexId, hasher(blake2_128_concat) (T::AccountId, T::AccountId) => T::Balance;
+        /// Removed indexes whose allowances and mint records are left for `clear_removed_index`.
+        /// Their ids cannot be reused until then.
+        RemovedIndexes get(fn is_removed): map hasher(blake2_128_concat) T::IndexId => bool;
+    }
+    add_extra_genesis {
+        config(indexes): Vec<(T::IndexId, StoneIndex<T::IndexId, T::AssetId, T::AccountId>)>;
+        /// Index units credited to holders, backed by components minted into the vault.
+        config(balances): Vec<(T::IndexId, T::AccountId, T::Balance)>;
+        build(|config: &GenesisConfig<T>| {
+            for (index_id, index) in config.indexes.iter() {
+                <Module<T>>::insert_new_index(*index_id, index.clone())
+                    .expect("genesis index ids must be unique");
+            }
+            for (index_id, who, amount) in config.balances.iter() {
+                <Module<T>>::mint_backed(*index_id, who.clone(), *amount)
+                    .expect("genesis index balances must belong to a genesis index and not overflow");
+            }
+        });
     }
 }
 
@@ -54,11 +294,66 @@ decl_event!(
         IndexId = <T as Config>::IndexId,
         Balance = <T as pallet_assets::Config>::Balance,
         AccountId = <T as frame_system::Config>::AccountId,
+        BlockNumber = <T as frame_system::Config>::BlockNumber,
+        Components = Vec<StoneIndexComponent<<T as pallet_assets::Config>::AssetId>>,
     {
+        /// A new index was created. \[index_id, owner\]
+        IndexCreated(IndexId, AccountId),
         // [index_id, amount, who]
         BuyIndex(IndexId, Balance, AccountId),
         SellIndex(IndexId, Balance, AccountId),
         TransferIndex(IndexId, AccountId, AccountId, Balance),
+        /// The vault of an index with outstanding supply was settled against a new
+        /// composition. \[index_id, old_components, new_components\]
+        Rebalanced(IndexId, Components, Components),
+        /// An update was proposed and will be enacted at the given block. \[index_id, enact_at\]
+        UpdateProposed(IndexId, BlockNumber),
+        /// A pending update was applied to the index. \[index_id\]
+        UpdateEnacted(IndexId),
+        /// A pending update was withdrawn by the owner. \[index_id\]
+        UpdateCancelled(IndexId),
+        /// A pending update fell due but could not be applied; it stays pending until it is
+        /// enacted manually or cancelled. \[index_id, error\]
+        UpdateFailed(IndexId, DispatchError),
+        /// The owner of an index changed. \[index_id, old_owner, new_owner\]
+        OwnershipTransferred(IndexId, AccountId, AccountId),
+        /// The owner offered an index to a new owner, who has to accept it.
+        /// \[index_id, owner, new_owner\]
+        OwnershipOffered(IndexId, AccountId, AccountId),
+        /// The owner set or removed the operator of an index. \[index_id, operator\]
+        OperatorSet(IndexId, Option<AccountId>),
+        /// The metadata of an index was set. \[index_id, symbol\]
+        MetadataSet(IndexId, Vec<u8>),
+        /// The metadata of an index was removed. \[index_id\]
+        MetadataCleared(IndexId),
+        /// The owner changed the supply cap of an index. \[index_id, max_supply\]
+        MaxSupplySet(IndexId, Option<Balance>),
+        /// The owner changed the mint limits of an index. \[index_id, per_account, per_block\]
+        MintLimitsSet(IndexId, Option<Balance>, Option<Balance>),
+        /// The owner changed the fees of an index. \[index_id, mint_fee, redeem_fee\]
+        FeesUpdated(IndexId, Permill, Permill),
+        /// A trade paid a fee in index units. \[index_id, payer, recipient, fee\]
+        FeeCharged(IndexId, AccountId, AccountId, Balance),
+        /// The owner changed the streaming fee of an index. \[index_id, annual_fee\]
+        StreamingFeeUpdated(IndexId, Perbill),
+        /// Streaming fee units were minted to the fee recipient. \[index_id, recipient, amount\]
+        StreamingFeeAccrued(IndexId, AccountId, Balance),
+        /// A holder changed how many units a spender may move on their behalf.
+        /// \[index_id, owner, spender, allowance\]
+        Approval(IndexId, AccountId, AccountId, Balance),
+        /// The net asset value of an index was recorded. \[index_id, nav, aum\]
+        NavUpdated(IndexId, Price, Price),
+        /// The status of an index changed. \[index_id, status\]
+        IndexStatusChanged(IndexId, IndexStatus),
+        /// An index was retired and its holders may claim their settlement. \[index_id\]
+        IndexRetired(IndexId),
+        /// A holder of a retired index burnt their units for their share of the vault.
+        /// \[index_id, who, units\]
+        SettlementClaimed(IndexId, AccountId, Balance),
+        /// A retired index without supply left was removed. \[index_id\]
+        IndexRemoved(IndexId),
+        /// Everything left behind by a removed index was cleared. \[index_id\]
+        IndexCleared(IndexId),
     }
 );
 
@@ -74,7 +369,67 @@ decl_error! {
         /// Transfer amount should be non-zero.
         TransferAmountZero,
         /// The index can only be updated by its owner
-        NotTheOwner
+        NotTheOwner,
+        /// The index can only be managed by its owner or operator.
+        NotTheOperator,
+        /// The index was not offered to the caller.
+        NotThePendingOwner,
+        /// A metadata field is longer than allowed.
+        BadMetadata,
+        /// The index has no metadata.
+        NoMetadata,
+        /// Buying would raise the supply of the index above its cap.
+        SupplyCapExceeded,
+        /// Buying would exceed the per-account or per-block mint limit of the index.
+        MintLimitExceeded,
+        /// The vault does not hold enough of an underlying asset to pay out the redemption.
+        InsufficientReserve,
+        /// An arithmetic operation overflowed.
+        Overflow,
+        /// An arithmetic operation underflowed.
+        Underflow,
+        /// An index with the given id already exists.
+        IndexAlreadyExists,
+        /// The id belongs to a removed index which still has to be cleared.
+        IndexNotCleared,
+        /// The index was not removed or has already been cleared.
+        NothingToClear,
+        /// An index must hold at least one component.
+        EmptyComposition,
+        /// Every component must have a non-zero weight.
+        ZeroWeight,
+        /// The same asset appears more than once in the composition.
+        DuplicateComponent,
+        /// A component refers to an asset which was never issued.
+        UnknownAsset,
+        /// The composition holds more than `MaxComponents` components.
+        TooManyComponents,
+        /// The index name is longer than `MaxNameLength`.
+        NameTooLong,
+        /// The index already has an update waiting to be enacted.
+        UpdateAlreadyPending,
+        /// The index has no pending update.
+        NoPendingUpdate,
+        /// The pending update cannot be enacted before its timelock expires.
+        UpdateNotDue,
+        /// The fee is above `MaxFee`.
+        FeeTooHigh,
+        /// The spender is not allowed to move that many units on behalf of the holder.
+        InsufficientAllowance,
+        /// The trade costs more or yields less than the limit set by the caller.
+        SlippageExceeded,
+        /// A component of the index has no fresh price.
+        PriceUnavailable,
+        /// The targets of a value-weighted composition must be non-zero and add up to 100%.
+        InvalidAllocation,
+        /// The index has more than `MAX_DECIMALS` decimals.
+        TooManyDecimals,
+        /// The status of the index does not allow the operation.
+        IndexPaused,
+        /// The index was retired, which cannot be undone.
+        IndexRetired,
+        /// Settlements can only be claimed from a retired index.
+        IndexNotRetired,
     }
 }
 
@@ -89,86 +444,582 @@ decl_module! {
         // Events must be initialized if they are used by the pallet.
         fn deposit_event() = default;
 
-        #[weight = 10_000 + T::DbWeight::get().writes(1)]
-        pub fn add_index(origin, #[compact] id: T::IndexId, name: Vec<u8>, components: Vec<StoneIndexComponent<T::AssetId>>) {
-            let _who = ensure_signed(origin)?;
+        /// The maximum number of components an index may hold.
+        const MaxComponents: u32 = T::MaxComponents::get();
+
+        /// The maximum length of an index name, in bytes.
+        const MaxNameLength: u32 = T::MaxNameLength::get();
+
+        /// The number of blocks between proposing and enacting an update.
+        const UpdateDelay: T::BlockNumber = T::UpdateDelay::get();
 
-            <Indexes<T>>::insert(&id, StoneIndex {
+        /// The highest mint or redeem fee an owner may set.
+        const MaxFee: Permill = T::MaxFee::get();
+
+        /// The number of blocks over which the annual streaming fee accrues.
+        const BlocksPerYear: T::BlockNumber = T::BlocksPerYear::get();
+
+        /// The highest annual streaming fee an owner may set.
+        const MaxStreamingFee: Perbill = T::MaxStreamingFee::get();
+
+        /// The deposit reserved for storing the metadata of an index.
+        const MetadataDepositBase: DepositBalanceOf<T> = T::MetadataDepositBase::get();
+
+        /// The additional deposit reserved per byte of metadata.
+        const MetadataDepositPerByte: DepositBalanceOf<T> = T::MetadataDepositPerByte::get();
+
+        /// The maximum length of an index symbol, in bytes.
+        const MaxSymbolLength: u32 = T::MaxSymbolLength::get();
+
+        /// The maximum length of an index description or URL, in bytes.
+        const MaxDescriptionLength: u32 = T::MaxDescriptionLength::get();
+
+        fn on_initialize(now: T::BlockNumber) -> Weight {
+            let due = <UpdateSchedule<T>>::take(now);
+            for id in due.iter() {
+                if let Err(e) = Self::enact_pending_update(*id) {
+                    Self::deposit_event(RawEvent::UpdateFailed(*id, e));
+                }
+            }
+            let touched = 1 + 8 * due.len() as Weight;
+            T::DbWeight::get().reads_writes(touched, touched)
+        }
+
+        /// Create a new index owned by the caller. The id is assigned from `NextIndexId` and
+        /// reported in the `IndexCreated` event.
+        #[weight = 10_000 + T::DbWeight::get().reads_writes(2, 2)]
+        pub fn add_index(origin,
+            name: Vec<u8>,
+            components: Vec<StoneIndexComponent<T::AssetId>>,
+            mode: CompositionMode,
+            decimals: u8
+        ) {
+            let who = T::CreateOrigin::ensure_origin(origin)?;
+            ensure!(decimals <= MAX_DECIMALS, Error::<T>::TooManyDecimals);
+            Self::ensure_valid_index(&name, &components, mode)?;
+            let components = Self::initial_components(components, mode)?;
+            let id = Self::next_index_id();
+
+            Self::insert_new_index(id, StoneIndex {
                 id,
                 name,
                 components,
-                owner: _who
-            });
+                owner: who.clone(),
+                mode,
+                decimals,
+                ..Default::default()
+            })?;
+            Self::deposit_event(RawEvent::IndexCreated(id, who));
         }
 
-        #[weight = 10_000 + T::DbWeight::get().writes(1)]
-        pub fn update_index(origin, #[compact] id: T::IndexId, name: Vec<u8>, components: Vec<StoneIndexComponent<T::AssetId>>) {
-            let _who = ensure_signed(origin)?;
-            ensure!(<Indexes<T>>::contains_key(&id), Error::<T>::IndexNotExist);
-            let index = Self::indexes(&id);
-            ensure!(_who == index.owner, Error::<T>::NotTheOwner);
+        /// Create an index under an explicit id on behalf of `owner`.
+        #[weight = 10_000 + T::DbWeight::get().reads_writes(2, 2)]
+        pub fn force_add_index(origin,
+            #[compact] id: T::IndexId,
+            name: Vec<u8>,
+            components: Vec<StoneIndexComponent<T::AssetId>>,
+            mode: CompositionMode,
+            decimals: u8,
+            owner: <T::Lookup as StaticLookup>::Source
+        ) {
+            T::AdminOrigin::ensure_origin(origin)?;
+            let owner = T::Lookup::lookup(owner)?;
+            ensure!(decimals <= MAX_DECIMALS, Error::<T>::TooManyDecimals);
+            Self::ensure_valid_index(&name, &components, mode)?;
+            let components = Self::initial_components(components, mode)?;
 
-            <Indexes<T>>::insert(&id, StoneIndex {
+            Self::insert_new_index(id, StoneIndex {
                 id,
                 name,
                 components,
-                owner: _who
+                owner: owner.clone(),
+                mode,
+                decimals,
+                ..Default::default()
+            })?;
+            Self::deposit_event(RawEvent::IndexCreated(id, owner));
+        }
+
+        /// Propose a new name and composition for an index. The update is enacted automatically
+        /// once `UpdateDelay` blocks have passed, giving holders time to exit.
+        #[weight = 10_000 + T::DbWeight::get().reads_writes(2, 2)]
+        pub fn propose_update(origin, #[compact] id: T::IndexId, name: Vec<u8>, components: Vec<StoneIndexComponent<T::AssetId>>) {
+            let who = ensure_signed(origin)?;
+            ensure!(<Indexes<T>>::contains_key(&id), Error::<T>::IndexNotExist);
+            Self::ensure_operator(&who, id)?;
+            ensure!(Self::indexes(&id).status != IndexStatus::Retired, Error::<T>::IndexRetired);
+            ensure!(!<PendingUpdates<T>>::contains_key(&id), Error::<T>::UpdateAlreadyPending);
+            Self::ensure_valid_index(&name, &components, Self::indexes(&id).mode)?;
+
+            let enact_at = <frame_system::Module<T>>::block_number() + T::UpdateDelay::get();
+            <PendingUpdates<T>>::insert(&id, PendingUpdate { name, components, enact_at });
+            <UpdateSchedule<T>>::append(enact_at, id);
+            Self::deposit_event(RawEvent::UpdateProposed(id, enact_at));
+        }
+
+        /// Enact a pending update whose timelock has expired, e.g. after its automatic
+        /// enactment failed because the owner could not cover the rebalance.
+        #[weight = 10_000 + T::DbWeight::get().reads_writes(4, 4)]
+        pub fn enact_update(origin, #[compact] id: T::IndexId) {
+            let who = ensure_signed(origin)?;
+            ensure!(<Indexes<T>>::contains_key(&id), Error::<T>::IndexNotExist);
+            Self::ensure_operator(&who, id)?;
+
+            Self::enact_pending_update(id)?;
+        }
+
+        /// Withdraw a pending update.
+        #[weight = 10_000 + T::DbWeight::get().reads_writes(2, 2)]
+        pub fn cancel_update(origin, #[compact] id: T::IndexId) {
+            let who = ensure_signed(origin)?;
+            ensure!(<Indexes<T>>::contains_key(&id), Error::<T>::IndexNotExist);
+            Self::ensure_operator(&who, id)?;
+            let update = Self::pending_updates(&id).ok_or(Error::<T>::NoPendingUpdate)?;
+
+            Self::remove_pending_update(id, update.enact_at);
+            Self::deposit_event(RawEvent::UpdateCancelled(id));
+        }
+
+        /// Set the mint and redeem fees of an index, each at most `MaxFee`.
+        #[weight = 10_000 + T::DbWeight::get().reads_writes(1, 1)]
+        pub fn set_fees(origin, #[compact] id: T::IndexId, mint_fee: Permill, redeem_fee: Permill) {
+            let who = ensure_signed(origin)?;
+            ensure!(<Indexes<T>>::contains_key(&id), Error::<T>::IndexNotExist);
+            ensure!(who == Self::indexes(&id).owner, Error::<T>::NotTheOwner);
+            ensure!(mint_fee <= T::MaxFee::get() && redeem_fee <= T::MaxFee::get(), Error::<T>::FeeTooHigh);
+
+            <Indexes<T>>::mutate(&id, |index| {
+                index.mint_fee = mint_fee;
+                index.redeem_fee = redeem_fee;
             });
+            Self::deposit_event(RawEvent::FeesUpdated(id, mint_fee, redeem_fee));
         }
 
-        #[weight = 10_000 + T::DbWeight::get().writes(1)]
-        pub fn buy_index(origin, #[compact] index_id: T::IndexId, #[compact] amount: T::Balance) {
-            let from = ensure_signed(origin.clone())?;
-            ensure!(<Indexes<T>>::contains_key(&index_id), Error::<T>::IndexNotExist);
-            let index = Self::indexes(&index_id);
+        /// Set the largest supply buying may raise an index to, or lift the cap with `None`.
+        /// A cap below the current supply only stops further buying.
+        #[weight = 10_000 + T::DbWeight::get().reads_writes(2, 1)]
+        pub fn set_max_supply(origin, #[compact] id: T::IndexId, max_supply: Option<T::Balance>) {
+            let who = ensure_signed(origin)?;
+            ensure!(<Indexes<T>>::contains_key(&id), Error::<T>::IndexNotExist);
+            ensure!(who == Self::indexes(&id).owner, Error::<T>::NotTheOwner);
+
+            <IndexMintLimits<T>>::mutate(&id, |limits| limits.max_supply = max_supply);
+            Self::deposit_event(RawEvent::MaxSupplySet(id, max_supply));
+        }
+
+        /// Set the most units of an index a single account may buy in total and the most that
+        /// may be bought within a block, `None` leaving either unlimited.
+        #[weight = 10_000 + T::DbWeight::get().reads_writes(2, 1)]
+        pub fn set_mint_limits(origin,
+            #[compact] id: T::IndexId,
+            per_account: Option<T::Balance>,
+            per_block: Option<T::Balance>
+        ) {
+            let who = ensure_signed(origin)?;
+            ensure!(<Indexes<T>>::contains_key(&id), Error::<T>::IndexNotExist);
+            ensure!(who == Self::indexes(&id).owner, Error::<T>::NotTheOwner);
+
+            <IndexMintLimits<T>>::mutate(&id, |limits| {
+                limits.per_account = per_account;
+                limits.per_block = per_block;
+            });
+            Self::deposit_event(RawEvent::MintLimitsSet(id, per_account, per_block));
+        }
+
+        /// Set the annual streaming fee of an index, at most `MaxStreamingFee`. The fee accrued
+        /// at the old rate is charged first.
+        #[weight = 10_000 + T::DbWeight::get().reads_writes(4, 4)]
+        #[transactional]
+        pub fn set_streaming_fee(origin, #[compact] id: T::IndexId, streaming_fee: Perbill) {
+            let who = ensure_signed(origin)?;
+            ensure!(<Indexes<T>>::contains_key(&id), Error::<T>::IndexNotExist);
+            ensure!(who == Self::indexes(&id).owner, Error::<T>::NotTheOwner);
+            ensure!(streaming_fee <= T::MaxStreamingFee::get(), Error::<T>::FeeTooHigh);
+
+            Self::accrue_streaming_fee(id)?;
+            <Indexes<T>>::mutate(&id, |index| index.streaming_fee = streaming_fee);
+            Self::deposit_event(RawEvent::StreamingFeeUpdated(id, streaming_fee));
+        }
+
+        /// Charge the streaming fee accrued on an index so far.
+        #[weight = 10_000 + T::DbWeight::get().reads_writes(4, 4)]
+        pub fn accrue_fees(origin, #[compact] id: T::IndexId) {
+            ensure_signed(origin)?;
+            ensure!(<Indexes<T>>::contains_key(&id), Error::<T>::IndexNotExist);
+
+            Self::accrue_streaming_fee(id)?;
+        }
+
+        /// Update an index immediately, bypassing the timelock. Any pending update is left
+        /// untouched.
+        #[weight = 10_000 + T::DbWeight::get().reads_writes(4, 4)]
+        pub fn force_update(origin, #[compact] id: T::IndexId, name: Vec<u8>, components: Vec<StoneIndexComponent<T::AssetId>>) {
+            T::AdminOrigin::ensure_origin(origin)?;
+
+            Self::apply_update(id, name, components)?;
+        }
+
+        /// Offer index `id` to `new_owner`, who becomes the owner once it calls
+        /// `accept_ownership`. A later offer replaces an earlier one.
+        #[weight = 10_000 + T::DbWeight::get().reads_writes(1, 1)]
+        pub fn transfer_ownership(origin, #[compact] id: T::IndexId, new_owner: <T::Lookup as StaticLookup>::Source) {
+            let who = ensure_signed(origin)?;
+            let new_owner = T::Lookup::lookup(new_owner)?;
+            ensure!(<Indexes<T>>::contains_key(&id), Error::<T>::IndexNotExist);
+            ensure!(who == Self::indexes(&id).owner, Error::<T>::NotTheOwner);
+
+            <PendingOwners<T>>::insert(&id, &new_owner);
+            Self::deposit_event(RawEvent::OwnershipOffered(id, who, new_owner));
+        }
+
+        /// Take over index `id` after its owner offered it to the caller, along with the deposit
+        /// of its metadata. The operator and any pending update of the previous owner are dropped.
+        #[weight = 10_000 + T::DbWeight::get().reads_writes(6, 6)]
+        pub fn accept_ownership(origin, #[compact] id: T::IndexId) {
+            let who = ensure_signed(origin)?;
+            ensure!(<Indexes<T>>::contains_key(&id), Error::<T>::IndexNotExist);
+            ensure!(Self::pending_owner(&id).as_ref() == Some(&who), Error::<T>::NotThePendingOwner);
+
+            Self::set_owner(id, who);
+        }
+
+        /// Hand an index over to `new_owner`, along with the deposit of its metadata. The operator
+        /// and any pending update of the previous owner are dropped.
+        #[weight = 10_000 + T::DbWeight::get().reads_writes(5, 5)]
+        pub fn force_transfer_ownership(origin, #[compact] id: T::IndexId, new_owner: <T::Lookup as StaticLookup>::Source) {
+            T::AdminOrigin::ensure_origin(origin)?;
+            let new_owner = T::Lookup::lookup(new_owner)?;
+            ensure!(<Indexes<T>>::contains_key(&id), Error::<T>::IndexNotExist);
+
+            Self::set_owner(id, new_owner);
+        }
+
+        /// Let `operator` update and pause index `id`, or nobody but the owner if `None`.
+        #[weight = 10_000 + T::DbWeight::get().reads_writes(1, 1)]
+        pub fn set_operator(origin, #[compact] id: T::IndexId, operator: Option<<T::Lookup as StaticLookup>::Source>) {
+            let who = ensure_signed(origin)?;
+            let operator = operator.map(T::Lookup::lookup).transpose()?;
+            ensure!(<Indexes<T>>::contains_key(&id), Error::<T>::IndexNotExist);
+            ensure!(who == Self::indexes(&id).owner, Error::<T>::NotTheOwner);
+
+            <Indexes<T>>::mutate(&id, |index| index.operator = operator.clone());
+            Self::deposit_event(RawEvent::OperatorSet(id, operator));
+        }
+
+        /// Set which operations index `id` allows, e.g. to pause minting during an incident.
+        #[weight = 10_000 + T::DbWeight::get().reads_writes(1, 1)]
+        pub fn set_index_status(origin, #[compact] id: T::IndexId, status: IndexStatus) {
+            let who = ensure_signed(origin)?;
+            ensure!(<Indexes<T>>::contains_key(&id), Error::<T>::IndexNotExist);
+            Self::ensure_operator(&who, id)?;
+
+            Self::do_set_index_status(id, status)?;
+        }
+
+        /// Set which operations index `id` allows on behalf of its owner.
+        #[weight = 10_000 + T::DbWeight::get().reads_writes(1, 1)]
+        pub fn force_set_index_status(origin, #[compact] id: T::IndexId, status: IndexStatus) {
+            T::AdminOrigin::ensure_origin(origin)?;
+            ensure!(<Indexes<T>>::contains_key(&id), Error::<T>::IndexNotExist);
+
+            Self::do_set_index_status(id, status)?;
+        }
+
+        /// Retire index `id`: units can no longer be bought or sold, and holders instead claim
+        /// their share of the vault through `claim_settlement`.
+        #[weight = 10_000 + T::DbWeight::get().reads_writes(6, 6)]
+        #[transactional]
+        pub fn retire_index(origin, #[compact] id: T::IndexId) {
+            let who = ensure_signed(origin)?;
+            ensure!(<Indexes<T>>::contains_key(&id), Error::<T>::IndexNotExist);
+            ensure!(who == Self::indexes(&id).owner, Error::<T>::NotTheOwner);
+
+            Self::do_retire_index(id)?;
+        }
+
+        /// Retire index `id` on behalf of its owner.
+        #[weight = 10_000 + T::DbWeight::get().reads_writes(6, 6)]
+        #[transactional]
+        pub fn force_retire_index(origin, #[compact] id: T::IndexId) {
+            T::AdminOrigin::ensure_origin(origin)?;
+            ensure!(<Indexes<T>>::contains_key(&id), Error::<T>::IndexNotExist);
+
+            Self::do_retire_index(id)?;
+        }
+
+        /// Burn every unit of retired index `id` held by the caller for a pro-rata share of each
+        /// component in the vault. The claim settling the last units removes the index.
+        #[weight = 10_000 + T::DbWeight::get().reads_writes(8, 8)]
+        #[transactional]
+        pub fn claim_settlement(origin, #[compact] id: T::IndexId) {
+            let who = ensure_signed(origin)?;
+            ensure!(<Indexes<T>>::contains_key(&id), Error::<T>::IndexNotExist);
+            let index = Self::indexes(&id);
+            ensure!(index.status == IndexStatus::Retired, Error::<T>::IndexNotRetired);
+            let units = Self::index_balance(id, &who);
+            ensure!(!units.is_zero(), Error::<T>::InsufficientIndexBalance);
+            let supply = Self::index_total_supply(id);
+            let vault = Self::vault_account(id);
 
             for comp in index.components.iter() {
-                let comp_value = amount * T::Balance::from(comp.weight);
-                let asset_balance = pallet_assets::Module::<T>::balance(comp.asset_id, from.clone());
-                ensure!(asset_balance >= comp_value, Error::<T>::InsufficientAssetBalance);
+                let held = pallet_assets::Module::<T>::balance(comp.asset_id, vault.clone());
+                let share = Self::mul_div(
+                    held.saturated_into(),
+                    units.saturated_into(),
+                    supply.saturated_into(),
+                    Rounding::Down,
+                )?;
+                if share != 0 {
+                    pallet_assets::Module::<T>::make_transfer(comp.asset_id, vault.clone(), who.clone(), share.saturated_into())?;
+                }
             }
+            Self::_burn(id, who.clone(), units)?;
+            Self::deposit_event(RawEvent::SettlementClaimed(id, who, units));
 
-            for comp in index.components.iter() {
-                let comp_value = amount * T::Balance::from(comp.weight);
-                pallet_assets::Module::<T>::burn(comp.asset_id, from.clone(), comp_value);
+            if units == supply {
+                Self::remove_index(id)?;
+            }
+        }
+
+        /// Clear up to `limit` allowances and mint records left behind by removed index `id`.
+        /// Its id may be reused once nothing is left.
+        #[weight = 10_000 + T::DbWeight::get().reads_writes(1 + 2 * *limit as u64, 1 + 2 * *limit as u64)]
+        pub fn clear_removed_index(origin, #[compact] id: T::IndexId, #[compact] limit: u32) {
+            ensure_signed(origin)?;
+            ensure!(<RemovedIndexes<T>>::contains_key(&id), Error::<T>::NothingToClear);
+
+            // Draining removes every entry the iterator yields.
+            let limit = limit as usize;
+            let cleared = <Allowances<T>>::drain_prefix(&id).take(limit).count();
+            let cleared = cleared + <MintedBy<T>>::drain_prefix(&id).take(limit - cleared).count();
+            if cleared < limit {
+                <RemovedIndexes<T>>::remove(&id);
+                Self::deposit_event(RawEvent::IndexCleared(id));
             }
-            <IndexBalances<T>>::mutate((&index_id, &from), |balance| *balance += amount);
+        }
 
-            Self::deposit_event(RawEvent::BuyIndex(index_id, amount, from));
+        /// Set the metadata of index `id`, reserving a deposit from the owner for every byte
+        /// stored. A deposit reserved for previous metadata is released.
+        #[weight = 10_000 + T::DbWeight::get().reads_writes(3, 3)]
+        #[transactional]
+        pub fn set_metadata(origin,
+            #[compact] id: T::IndexId,
+            symbol: Vec<u8>,
+            description: Vec<u8>,
+            url: Option<Vec<u8>>
+        ) {
+            let who = ensure_signed(origin)?;
+            ensure!(<Indexes<T>>::contains_key(&id), Error::<T>::IndexNotExist);
+            let index = Self::indexes(&id);
+            ensure!(who == index.owner, Error::<T>::NotTheOwner);
+            ensure!(symbol.len() <= T::MaxSymbolLength::get() as usize, Error::<T>::BadMetadata);
+            let max_description = T::MaxDescriptionLength::get() as usize;
+            ensure!(description.len() <= max_description, Error::<T>::BadMetadata);
+            ensure!(url.as_ref().map_or(true, |url| url.len() <= max_description), Error::<T>::BadMetadata);
+
+            let bytes = symbol.len() + description.len() + url.as_ref().map_or(0, |url| url.len());
+            let deposit = T::MetadataDepositPerByte::get()
+                .saturating_mul((bytes as u32).into())
+                .saturating_add(T::MetadataDepositBase::get());
+            if let Some(old) = Self::index_metadata(&id) {
+                T::Currency::unreserve(&old.depositor, old.deposit);
+            }
+            T::Currency::reserve(&who, deposit)?;
+
+            <IndexMetadata<T>>::insert(&id, StoneIndexMetadata {
+                symbol: symbol.clone(),
+                decimals: index.decimals,
+                description,
+                url,
+                depositor: who,
+                deposit,
+            });
+            Self::deposit_event(RawEvent::MetadataSet(id, symbol));
         }
 
+        /// Remove the metadata of index `id` and release its deposit.
+        #[weight = 10_000 + T::DbWeight::get().reads_writes(2, 2)]
+        pub fn clear_metadata(origin, #[compact] id: T::IndexId) {
+            let who = ensure_signed(origin)?;
+            ensure!(<Indexes<T>>::contains_key(&id), Error::<T>::IndexNotExist);
+            ensure!(who == Self::indexes(&id).owner, Error::<T>::NotTheOwner);
+            ensure!(<IndexMetadata<T>>::contains_key(&id), Error::<T>::NoMetadata);
+
+            Self::remove_metadata(id);
+        }
+
+        /// Buy `amount` units of the index, moving the underlying assets into its vault. The
+        /// mint fee is kept from the bought units and credited to the fee recipient.
         #[weight = 10_000 + T::DbWeight::get().writes(1)]
-        pub fn sell_index(origin, #[compact] index_id: T::IndexId, #[compact] amount: T::Balance) {
+        #[transactional]
+        pub fn buy_index(origin, #[compact] index_id: T::IndexId, #[compact] amount: T::Balance) {
+            let from = ensure_signed(origin)?;
+            Self::do_buy(from, index_id, amount)?;
+        }
+
+        /// Buy `amount` units of the index paying only with `pay_asset`, which is swapped into
+        /// each component through `Swap`. Fails entirely if more than `max_pay` would be spent.
+        #[weight = 10_000 + T::DbWeight::get().reads_writes(4, 4)]
+        #[transactional]
+        pub fn buy_index_with(origin,
+            #[compact] index_id: T::IndexId,
+            #[compact] amount: T::Balance,
+            pay_asset: T::AssetId,
+            #[compact] max_pay: T::Balance
+        ) {
             let from = ensure_signed(origin)?;
             ensure!(<Indexes<T>>::contains_key(&index_id), Error::<T>::IndexNotExist);
+            ensure!(Self::indexes(&index_id).status.allows_mint(), Error::<T>::IndexPaused);
+            Self::accrue_streaming_fee(index_id)?;
             let index = Self::indexes(&index_id);
-            let index_balance = Self::index_balances((&index_id, &from));
-            ensure!(index_balance >= amount, Error::<T>::InsufficientIndexBalance);
 
+            let mut paid: T::Balance = Zero::zero();
             for comp in index.components.iter() {
-                let comp_value = amount * T::Balance::from(comp.weight);
-                pallet_assets::Module::<T>::mint(comp.asset_id, from.clone(), comp_value);
+                let needed = Self::component_amount(index_id, amount, comp, Rounding::Up)?;
+                let cost = if comp.asset_id == pay_asset || needed.is_zero() {
+                    needed
+                } else {
+                    T::Swap::swap_exact_out(&from, pay_asset, comp.asset_id, needed, max_pay.saturating_sub(paid))?
+                };
+                paid = paid.checked_add(&cost).ok_or(Error::<T>::Overflow)?;
+                ensure!(paid <= max_pay, Error::<T>::SlippageExceeded);
             }
-            <IndexBalances<T>>::mutate((&index_id, &from), |balance| *balance -= amount);
 
-            Self::deposit_event(RawEvent::SellIndex(index_id, amount, from));
+            Self::do_buy(from, index_id, amount)?;
         }
 
+        /// Sell `amount` units of the index, paying the underlying assets out of its vault. The
+        /// redeem fee is transferred to the fee recipient and only the rest is redeemed.
         #[weight = 10_000 + T::DbWeight::get().writes(1)]
+        #[transactional]
+        pub fn sell_index(origin, #[compact] index_id: T::IndexId, #[compact] amount: T::Balance) {
+            let from = ensure_signed(origin)?;
+            Self::do_sell(from, index_id, amount)?;
+        }
+
+        /// Sell `amount` units of the index for `receive_asset` alone, swapping each paid out
+        /// component through `Swap`. Fails entirely if less than `min_receive` would be received.
+        #[weight = 10_000 + T::DbWeight::get().reads_writes(4, 4)]
+        #[transactional]
+        pub fn sell_index_to(origin,
+            #[compact] index_id: T::IndexId,
+            #[compact] amount: T::Balance,
+            receive_asset: T::AssetId,
+            #[compact] min_receive: T::Balance
+        ) {
+            let from = ensure_signed(origin)?;
+            let payouts = Self::do_sell(from.clone(), index_id, amount)?;
+
+            let mut received: T::Balance = Zero::zero();
+            for (asset_id, paid_out) in payouts {
+                let proceeds = if asset_id == receive_asset || paid_out.is_zero() {
+                    paid_out
+                } else {
+                    T::Swap::swap_exact_in(&from, asset_id, receive_asset, paid_out, Zero::zero())?
+                };
+                received = received.checked_add(&proceeds).ok_or(Error::<T>::Overflow)?;
+            }
+            ensure!(received >= min_receive, Error::<T>::SlippageExceeded);
+        }
+
+        #[weight = 10_000 + T::DbWeight::get().writes(1)]
+        #[transactional]
         pub fn transfer(origin,
             #[compact] id: T::IndexId,
             target: <T::Lookup as StaticLookup>::Source,
             #[compact] amount: T::Balance
         ) {
             let origin = ensure_signed(origin)?;
-            let origin_account = (id, origin.clone());
-            let origin_balance = <IndexBalances<T>>::get(&origin_account);
             let target = T::Lookup::lookup(target)?;
+            ensure!(<Indexes<T>>::contains_key(&id), Error::<T>::IndexNotExist);
+            ensure!(Self::indexes(&id).status.allows_transfer(), Error::<T>::IndexPaused);
+            ensure!(!amount.is_zero(), Error::<T>::TransferAmountZero);
+            ensure!(Self::index_balance(id, &origin) >= amount, Error::<T>::InsufficientIndexBalance);
+
+            Self::accrue_streaming_fee(id)?;
+            Self::_transfer(id, origin.clone(), target.clone(), amount)?;
+            Self::deposit_event(RawEvent::TransferIndex(id, origin, target, amount));
+        }
+
+        /// Allow `spender` to move up to `amount` units of the caller's index `id`, replacing
+        /// any previous allowance. An amount of zero revokes the allowance.
+        #[weight = 10_000 + T::DbWeight::get().reads_writes(1, 1)]
+        pub fn approve(origin,
+            #[compact] id: T::IndexId,
+            spender: <T::Lookup as StaticLookup>::Source,
+            #[compact] amount: T::Balance
+        ) {
+            let owner = ensure_signed(origin)?;
+            let spender = T::Lookup::lookup(spender)?;
+            ensure!(<Indexes<T>>::contains_key(&id), Error::<T>::IndexNotExist);
+
+            Self::set_allowance(id, owner, spender, amount);
+        }
+
+        /// Raise the allowance of `spender` over the caller's index `id` by `amount`.
+        #[weight = 10_000 + T::DbWeight::get().reads_writes(2, 1)]
+        pub fn increase_allowance(origin,
+            #[compact] id: T::IndexId,
+            spender: <T::Lookup as StaticLookup>::Source,
+            #[compact] amount: T::Balance
+        ) {
+            let owner = ensure_signed(origin)?;
+            let spender = T::Lookup::lookup(spender)?;
+            ensure!(<Indexes<T>>::contains_key(&id), Error::<T>::IndexNotExist);
+
+            let allowance = Self::allowance(id, (&owner, &spender)).checked_add(&amount).ok_or(Error::<T>::Overflow)?;
+            Self::set_allowance(id, owner, spender, allowance);
+        }
+
+        /// Lower the allowance of `spender` over the caller's index `id` by `amount`.
+        #[weight = 10_000 + T::DbWeight::get().reads_writes(2, 1)]
+        pub fn decrease_allowance(origin,
+            #[compact] id: T::IndexId,
+            spender: <T::Lookup as StaticLookup>::Source,
+            #[compact] amount: T::Balance
+        ) {
+            let owner = ensure_signed(origin)?;
+            let spender = T::Lookup::lookup(spender)?;
+            ensure!(<Indexes<T>>::contains_key(&id), Error::<T>::IndexNotExist);
+
+            let allowance = Self::allowance(id, (&owner, &spender)).checked_sub(&amount).ok_or(Error::<T>::Underflow)?;
+            Self::set_allowance(id, owner, spender, allowance);
+        }
+
+        /// Move `amount` units of index `id` from `owner` to `target`, spending the caller's
+        /// allowance.
+        #[weight = 10_000 + T::DbWeight::get().reads_writes(4, 4)]
+        #[transactional]
+        pub fn transfer_from(origin,
+            #[compact] id: T::IndexId,
+            owner: <T::Lookup as StaticLookup>::Source,
+            target: <T::Lookup as StaticLookup>::Source,
+            #[compact] amount: T::Balance
+        ) {
+            let spender = ensure_signed(origin)?;
+            let owner = T::Lookup::lookup(owner)?;
+            let target = T::Lookup::lookup(target)?;
+            ensure!(<Indexes<T>>::contains_key(&id), Error::<T>::IndexNotExist);
+            ensure!(Self::indexes(&id).status.allows_transfer(), Error::<T>::IndexPaused);
             ensure!(!amount.is_zero(), Error::<T>::TransferAmountZero);
-            ensure!(origin_balance >= amount, Error::<T>::InsufficientIndexBalance);
+            let allowance = Self::allowance(id, (&owner, &spender));
+            ensure!(allowance >= amount, Error::<T>::InsufficientAllowance);
+            ensure!(Self::index_balance(id, &owner) >= amount, Error::<T>::InsufficientIndexBalance);
+
+            Self::accrue_streaming_fee(id)?;
+            Self::_transfer(id, owner.clone(), target.clone(), amount)?;
+            Self::set_allowance(id, owner.clone(), spender, allowance - amount);
+            Self::deposit_event(RawEvent::TransferIndex(id, owner, target, amount));
+        }
 
-            Self::deposit_event(RawEvent::TransferIndex(id, origin.clone(), target.clone(), amount));
-            Self::_transfer(id, origin, target, amount);
+        /// Record the current net asset value per unit and the assets under management of
+        /// index `id` in a `NavUpdated` event.
+        #[weight = 10_000 + T::DbWeight::get().reads(8)]
+        pub fn update_nav(origin, #[compact] id: T::IndexId) {
+            ensure_signed(origin)?;
+            let nav = Self::nav(id)?;
+            let aum = Self::aum(id)?;
+            Self::deposit_event(RawEvent::NavUpdated(id, nav, aum));
         }
     }
 }
@@ -180,12 +1031,584 @@ impl<T: Config> Module<T> {
         Self::indexes(id)
     }
 
-    pub fn _mint(index_id: T::IndexId, account: T::AccountId, amount: T::Balance) {
-        <IndexBalances<T>>::mutate((index_id, account), |balance| *balance += amount);
+    pub fn get_metadata(id: &T::IndexId) -> Option<StoneIndexMetadata<DepositBalanceOf<T>, T::AccountId>> {
+        Self::index_metadata(id)
+    }
+
+    // The metadata deposit is reserved from the new owner instead. Metadata the new owner
+    // cannot cover is removed rather than holding up the transfer.
+    fn move_metadata_deposit(id: T::IndexId, new_owner: &T::AccountId) {
+        if let Some(mut metadata) = Self::index_metadata(&id) {
+            T::Currency::unreserve(&metadata.depositor, metadata.deposit);
+            if T::Currency::reserve(new_owner, metadata.deposit).is_ok() {
+                metadata.depositor = new_owner.clone();
+                <IndexMetadata<T>>::insert(&id, metadata);
+            } else {
+                <IndexMetadata<T>>::remove(&id);
+                Self::deposit_event(RawEvent::MetadataCleared(id));
+            }
+        }
+    }
+
+    fn remove_metadata(id: T::IndexId) {
+        if let Some(metadata) = <IndexMetadata<T>>::take(&id) {
+            T::Currency::unreserve(&metadata.depositor, metadata.deposit);
+            Self::deposit_event(RawEvent::MetadataCleared(id));
+        }
+    }
+
+    /// Move the components of `amount` units of index `index_id` from `from` into the vault
+    /// and mint the units, less the mint fee.
+    fn do_buy(from: T::AccountId, index_id: T::IndexId, amount: T::Balance) -> DispatchResult {
+        ensure!(<Indexes<T>>::contains_key(&index_id), Error::<T>::IndexNotExist);
+        ensure!(Self::indexes(&index_id).status.allows_mint(), Error::<T>::IndexPaused);
+        Self::accrue_streaming_fee(index_id)?;
+        Self::record_mint(index_id, &from, amount)?;
+        let index = Self::indexes(&index_id);
+        let vault = Self::vault_account(index_id);
+
+        for comp in index.components.iter() {
+            let comp_value = Self::component_amount(index_id, amount, comp, Rounding::Up)?;
+            let asset_balance = pallet_assets::Module::<T>::balance(comp.asset_id, from.clone());
+            ensure!(asset_balance >= comp_value, Error::<T>::InsufficientAssetBalance);
+        }
+
+        for comp in index.components.iter() {
+            let comp_value = Self::component_amount(index_id, amount, comp, Rounding::Up)?;
+            pallet_assets::Module::<T>::make_transfer(comp.asset_id, from.clone(), vault.clone(), comp_value)?;
+        }
+
+        let fee = index.mint_fee.mul_ceil(amount);
+        Self::_mint(index_id, from.clone(), amount - fee)?;
+        if !fee.is_zero() {
+            let recipient = Self::fee_recipient(&index);
+            Self::_mint(index_id, recipient.clone(), fee)?;
+            Self::deposit_event(RawEvent::FeeCharged(index_id, from.clone(), recipient, fee));
+        }
+
+        Self::deposit_event(RawEvent::BuyIndex(index_id, amount, from));
+        Ok(())
+    }
+
+    /// Count `amount` bought units of index `index_id` against its supply cap and the mint
+    /// limits of `who` and the current block.
+    fn record_mint(index_id: T::IndexId, who: &T::AccountId, amount: T::Balance) -> DispatchResult {
+        let limits = Self::mint_limits(&index_id);
+        if let Some(max_supply) = limits.max_supply {
+            let supply = Self::index_total_supply(index_id).checked_add(&amount).ok_or(Error::<T>::Overflow)?;
+            ensure!(supply <= max_supply, Error::<T>::SupplyCapExceeded);
+        }
+
+        let minted = Self::minted_by(&index_id, who).checked_add(&amount).ok_or(Error::<T>::Overflow)?;
+        ensure!(limits.per_account.map_or(true, |limit| minted <= limit), Error::<T>::MintLimitExceeded);
+
+        let now = <frame_system::Module<T>>::block_number();
+        let (block, in_block) = Self::minted_in_block(&index_id);
+        let in_block = if block == now { in_block } else { Zero::zero() };
+        let in_block = in_block.checked_add(&amount).ok_or(Error::<T>::Overflow)?;
+        ensure!(limits.per_block.map_or(true, |limit| in_block <= limit), Error::<T>::MintLimitExceeded);
+
+        <MintedBy<T>>::insert(&index_id, who, minted);
+        <MintedInBlock<T>>::insert(&index_id, (now, in_block));
+        Ok(())
+    }
+
+    /// Burn `amount` units of index `index_id` held by `from`, less the redeem fee, and pay
+    /// the components they back out of the vault. Returns the amount paid of each component.
+    fn do_sell(from: T::AccountId, index_id: T::IndexId, amount: T::Balance) -> Result<Vec<(T::AssetId, T::Balance)>, DispatchError> {
+        ensure!(<Indexes<T>>::contains_key(&index_id), Error::<T>::IndexNotExist);
+        ensure!(Self::indexes(&index_id).status.allows_redeem(), Error::<T>::IndexPaused);
+        Self::accrue_streaming_fee(index_id)?;
+        let index = Self::indexes(&index_id);
+        let index_balance = Self::index_balance(index_id, &from);
+        ensure!(index_balance >= amount, Error::<T>::InsufficientIndexBalance);
+        let vault = Self::vault_account(index_id);
+        let fee = index.redeem_fee.mul_ceil(amount);
+        let redeemed = amount - fee;
+
+        let mut payouts = Vec::with_capacity(index.components.len());
+        for comp in index.components.iter() {
+            let comp_value = Self::component_amount(index_id, redeemed, comp, Rounding::Down)?;
+            let reserve = pallet_assets::Module::<T>::balance(comp.asset_id, vault.clone());
+            ensure!(reserve >= comp_value, Error::<T>::InsufficientReserve);
+            payouts.push((comp.asset_id, comp_value));
+        }
+
+        if !fee.is_zero() {
+            let recipient = Self::fee_recipient(&index);
+            Self::_transfer(index_id, from.clone(), recipient.clone(), fee)?;
+            Self::deposit_event(RawEvent::FeeCharged(index_id, from.clone(), recipient, fee));
+        }
+        for (asset_id, comp_value) in payouts.iter() {
+            pallet_assets::Module::<T>::make_transfer(*asset_id, vault.clone(), from.clone(), *comp_value)?;
+        }
+        Self::_burn(index_id, from.clone(), redeemed)?;
+
+        Self::deposit_event(RawEvent::SellIndex(index_id, amount, from));
+        Ok(payouts)
+    }
+
+    /// Ensure `who` is the owner or the operator of index `id`.
+    fn ensure_operator(who: &T::AccountId, id: T::IndexId) -> DispatchResult {
+        let index = Self::indexes(&id);
+        ensure!(*who == index.owner || index.operator.as_ref() == Some(who), Error::<T>::NotTheOperator);
+        Ok(())
+    }
+
+    // Any offer of the index to another owner lapses.
+    fn set_owner(id: T::IndexId, new_owner: T::AccountId) {
+        Self::move_metadata_deposit(id, &new_owner);
+        let (old_owner, old_operator) = <Indexes<T>>::mutate(&id, |index| {
+            (sp_std::mem::replace(&mut index.owner, new_owner.clone()), index.operator.take())
+        });
+        <PendingOwners<T>>::remove(&id);
+        if old_operator.is_some() {
+            Self::deposit_event(RawEvent::OperatorSet(id, None));
+        }
+        if let Some(update) = Self::pending_updates(&id) {
+            Self::remove_pending_update(id, update.enact_at);
+            Self::deposit_event(RawEvent::UpdateCancelled(id));
+        }
+        Self::deposit_event(RawEvent::OwnershipTransferred(id, old_owner, new_owner));
+    }
+
+    // Retirement is final and only entered through `retire_index`.
+    fn do_set_index_status(id: T::IndexId, status: IndexStatus) -> DispatchResult {
+        ensure!(Self::indexes(&id).status != IndexStatus::Retired, Error::<T>::IndexRetired);
+        ensure!(status != IndexStatus::Retired, Error::<T>::IndexRetired);
+
+        <Indexes<T>>::mutate(&id, |index| index.status = status);
+        Self::deposit_event(RawEvent::IndexStatusChanged(id, status));
+        Ok(())
+    }
+
+    fn do_retire_index(id: T::IndexId) -> DispatchResult {
+        ensure!(Self::indexes(&id).status != IndexStatus::Retired, Error::<T>::IndexRetired);
+        // The fee recipient is paid up to retirement and settles like any other holder.
+        Self::accrue_streaming_fee(id)?;
+        if let Some(update) = Self::pending_updates(&id) {
+            Self::remove_pending_update(id, update.enact_at);
+        }
+
+        <Indexes<T>>::mutate(&id, |index| index.status = IndexStatus::Retired);
+        Self::deposit_event(RawEvent::IndexRetired(id));
+        if Self::index_total_supply(id).is_zero() {
+            Self::remove_index(id)?;
+        }
+        Ok(())
+    }
+
+    /// Remove index `id` and everything stored for it. Whatever the vault still holds of the
+    /// components is handed to the owner.
+    fn remove_index(id: T::IndexId) -> DispatchResult {
+        let index = Self::indexes(&id);
+        let vault = Self::vault_account(id);
+        for comp in index.components.iter() {
+            let held = pallet_assets::Module::<T>::balance(comp.asset_id, vault.clone());
+            if !held.is_zero() {
+                pallet_assets::Module::<T>::make_transfer(comp.asset_id, vault.clone(), index.owner.clone(), held)?;
+            }
+        }
+
+        Self::remove_metadata(id);
+        <AssetIndex<T>>::remove(index.asset_id);
+        <Indexes<T>>::remove(&id);
+        <PendingOwners<T>>::remove(&id);
+        <UnitMultiplier<T>>::remove(&id);
+        <LastFeeAccrual<T>>::remove(&id);
+        <IndexMintLimits<T>>::remove(&id);
+        <MintedInBlock<T>>::remove(&id);
+        // There may be any number of allowances and mint records, so they are cleared separately.
+        <RemovedIndexes<T>>::insert(&id, true);
+        Self::deposit_event(RawEvent::IndexRemoved(id));
+        Ok(())
+    }
+
+    /// Apply the pending update of index `id` if its timelock has expired.
+    fn enact_pending_update(id: T::IndexId) -> DispatchResult {
+        let update = Self::pending_updates(&id).ok_or(Error::<T>::NoPendingUpdate)?;
+        ensure!(update.enact_at <= <frame_system::Module<T>>::block_number(), Error::<T>::UpdateNotDue);
+
+        Self::apply_update(id, update.name, update.components)?;
+        Self::remove_pending_update(id, update.enact_at);
+        Self::deposit_event(RawEvent::UpdateEnacted(id));
+        Ok(())
+    }
+
+    fn remove_pending_update(id: T::IndexId, enact_at: T::BlockNumber) {
+        <PendingUpdates<T>>::remove(&id);
+        <UpdateSchedule<T>>::mutate_exists(enact_at, |scheduled| {
+            if let Some(ids) = scheduled {
+                ids.retain(|scheduled_id| *scheduled_id != id);
+                if ids.is_empty() {
+                    *scheduled = None;
+                }
+            }
+        });
+    }
+
+    /// Replace the name and composition of index `id`. If units are outstanding, the owner
+    /// deposits into or withdraws from the vault whatever the new composition requires.
+    #[transactional]
+    fn apply_update(id: T::IndexId, name: Vec<u8>, mut components: Vec<StoneIndexComponent<T::AssetId>>) -> DispatchResult {
+        ensure!(<Indexes<T>>::contains_key(&id), Error::<T>::IndexNotExist);
+        let mut index = Self::indexes(&id);
+        ensure!(index.status != IndexStatus::Retired, Error::<T>::IndexRetired);
+        Self::ensure_valid_index(&name, &components, index.mode)?;
+        if index.mode == CompositionMode::ValueWeighted {
+            // The new quantities keep a unit worth the same at current prices.
+            let unit_value = Self::composition_value(&index.components)?;
+            Self::derive_weights(&mut components, unit_value)?;
+        }
+
+        if index.components != components && !Self::index_total_supply(id).is_zero() {
+            Self::rebalance(id, &index.owner, &index.components, &components)?;
+            Self::deposit_event(RawEvent::Rebalanced(id, index.components.clone(), components.clone()));
+        }
+        index.name = name;
+        index.components = components;
+        <Indexes<T>>::insert(&id, index);
+        Ok(())
+    }
+
+    /// The amount of `asset_id` the vault of index `id` must hold to back `supply` units of a
+    /// composition.
+    fn required_holding(
+        id: T::IndexId,
+        supply: T::Balance,
+        components: &[StoneIndexComponent<T::AssetId>],
+        asset_id: T::AssetId,
+    ) -> Result<T::Balance, DispatchError> {
+        match components.iter().find(|comp| comp.asset_id == asset_id) {
+            Some(comp) => Self::component_amount(id, supply, comp, Rounding::Up),
+            None => Ok(Zero::zero()),
+        }
+    }
+
+    /// Settle the vault of index `id` so that it backs the current supply under `new`. Every
+    /// asset of `old` or `new` is brought to exactly what `new` requires: shortfalls are paid
+    /// in by `owner`, surpluses are paid out to `owner`.
+    fn rebalance(
+        id: T::IndexId,
+        owner: &T::AccountId,
+        old: &[StoneIndexComponent<T::AssetId>],
+        new: &[StoneIndexComponent<T::AssetId>],
+    ) -> DispatchResult {
+        let supply = Self::index_total_supply(id);
+        let vault = Self::vault_account(id);
+
+        let mut asset_ids: Vec<T::AssetId> = old.iter().chain(new.iter()).map(|comp| comp.asset_id).collect();
+        asset_ids.sort();
+        asset_ids.dedup();
+
+        let mut deposits = Vec::new();
+        let mut withdrawals = Vec::new();
+        for asset_id in asset_ids {
+            let held = pallet_assets::Module::<T>::balance(asset_id, vault.clone());
+            let needed = Self::required_holding(id, supply, new, asset_id)?;
+            if needed > held {
+                let delta = needed - held;
+                let owner_balance = pallet_assets::Module::<T>::balance(asset_id, owner.clone());
+                ensure!(owner_balance >= delta, Error::<T>::InsufficientAssetBalance);
+                deposits.push((asset_id, delta));
+            } else if held > needed {
+                withdrawals.push((asset_id, held - needed));
+            }
+        }
+
+        for (asset_id, delta) in deposits {
+            pallet_assets::Module::<T>::make_transfer(asset_id, owner.clone(), vault.clone(), delta)?;
+        }
+        for (asset_id, delta) in withdrawals {
+            pallet_assets::Module::<T>::make_transfer(asset_id, vault.clone(), owner.clone(), delta)?;
+        }
+        Ok(())
+    }
+
+    /// Check a name and composition against the rules every index must satisfy. The weights
+    /// of a value-weighted composition are derived later, so only its targets are checked.
+    pub fn ensure_valid_index(
+        name: &[u8],
+        components: &[StoneIndexComponent<T::AssetId>],
+        mode: CompositionMode,
+    ) -> DispatchResult {
+        ensure!(name.len() <= T::MaxNameLength::get() as usize, Error::<T>::NameTooLong);
+        ensure!(!components.is_empty(), Error::<T>::EmptyComposition);
+        ensure!(components.len() <= T::MaxComponents::get() as usize, Error::<T>::TooManyComponents);
+
+        let mut asset_ids = Vec::with_capacity(components.len());
+        let mut allocated: u32 = 0;
+        for comp in components.iter() {
+            match mode {
+                CompositionMode::Quantity => ensure!(!comp.weight.is_zero(), Error::<T>::ZeroWeight),
+                CompositionMode::ValueWeighted => {
+                    ensure!(comp.target.deconstruct() > 0, Error::<T>::InvalidAllocation);
+                    allocated = allocated.saturating_add(comp.target.deconstruct());
+                }
+            }
+            ensure!(pallet_assets::Module::<T>::asset_exists(comp.asset_id), Error::<T>::UnknownAsset);
+            asset_ids.push(comp.asset_id);
+        }
+        if mode == CompositionMode::ValueWeighted {
+            ensure!(allocated == Permill::ACCURACY, Error::<T>::InvalidAllocation);
+        }
+        asset_ids.sort();
+        ensure!(asset_ids.windows(2).all(|pair| pair[0] != pair[1]), Error::<T>::DuplicateComponent);
+        Ok(())
+    }
+
+    /// The composition a new index starts with: value-weighted components get quantities
+    /// worth `InitialUnitValue` per unit at current prices.
+    fn initial_components(
+        mut components: Vec<StoneIndexComponent<T::AssetId>>,
+        mode: CompositionMode,
+    ) -> Result<Vec<StoneIndexComponent<T::AssetId>>, DispatchError> {
+        if mode == CompositionMode::ValueWeighted {
+            Self::derive_weights(&mut components, T::InitialUnitValue::get())?;
+        }
+        Ok(components)
+    }
+
+    /// Set the weight of each component to the amount worth its target share of `unit_value`
+    /// at current prices.
+    fn derive_weights(components: &mut [StoneIndexComponent<T::AssetId>], unit_value: Price) -> DispatchResult {
+        for comp in components.iter_mut() {
+            let price = Self::component_price(comp.asset_id)?;
+            let share = Price::saturating_from_rational(comp.target.deconstruct(), Permill::ACCURACY);
+            let weight = unit_value
+                .checked_mul(&share)
+                .and_then(|value| value.checked_div(&price))
+                .ok_or(Error::<T>::Overflow)?;
+            ensure!(!weight.is_zero(), Error::<T>::ZeroWeight);
+            comp.weight = weight;
+        }
+        Ok(())
+    }
+
+    /// Store a new index under `id` and create the asset representing its units, keeping
+    /// `NextIndexId` ahead of every id in use.
+    fn insert_new_index(id: T::IndexId, mut index: StoneIndex<T::IndexId, T::AssetId, T::AccountId>) -> DispatchResult {
+        ensure!(!<Indexes<T>>::contains_key(&id), Error::<T>::IndexAlreadyExists);
+        ensure!(!<RemovedIndexes<T>>::contains_key(&id), Error::<T>::IndexNotCleared);
+        let following_id = id.checked_add(&One::one()).ok_or(Error::<T>::Overflow)?;
+        index.asset_id = pallet_assets::Module::<T>::create_asset()?;
+
+        if following_id > Self::next_index_id() {
+            <NextIndexId<T>>::put(following_id);
+        }
+        <AssetIndex<T>>::insert(index.asset_id, id);
+        <Indexes<T>>::insert(&id, index);
+        <LastFeeAccrual<T>>::insert(&id, <frame_system::Module<T>>::block_number());
+        Ok(())
     }
 
-    pub fn _transfer(index_id: T::IndexId, from: T::AccountId, to: T::AccountId, amount: T::Balance) {
-        <IndexBalances<T>>::mutate((index_id, from), |balance| *balance -= amount);
-        <IndexBalances<T>>::mutate((index_id, to), |balance| *balance += amount);
+    /// Mint the streaming fee accrued on index `id` since it was last charged to the fee
+    /// recipient, diluting the holders. The fee grows linearly with the number of blocks
+    /// elapsed; while it still rounds down to nothing the accrual period keeps running.
+    pub fn accrue_streaming_fee(id: T::IndexId) -> DispatchResult {
+        let now = <frame_system::Module<T>>::block_number();
+        let last = Self::last_fee_accrual(&id);
+        if now <= last {
+            return Ok(());
+        }
+
+        let index = Self::indexes(&id);
+        let annual_fee = index.streaming_fee.mul_floor(Self::index_total_supply(id));
+        // Retired indexes no longer charge the fee.
+        if annual_fee.is_zero() || index.status == IndexStatus::Retired {
+            <LastFeeAccrual<T>>::insert(&id, now);
+            return Ok(());
+        }
+
+        let elapsed = T::Balance::from((now - last).saturated_into::<u32>());
+        let year = T::Balance::from(T::BlocksPerYear::get().saturated_into::<u32>()).max(One::one());
+        let accrued = annual_fee.checked_mul(&elapsed).ok_or(Error::<T>::Overflow)? / year;
+        if accrued.is_zero() {
+            return Ok(());
+        }
+
+        // Every unit is now backed by a correspondingly smaller share of the vault.
+        let supply: u128 = Self::index_total_supply(id).saturated_into();
+        let new_supply = supply.checked_add(accrued.saturated_into()).ok_or(Error::<T>::Overflow)?;
+        let dilution = FixedU128::saturating_from_rational(supply, new_supply);
+        let multiplier = Self::unit_multiplier(id).checked_mul(&dilution).ok_or(Error::<T>::Overflow)?;
+
+        let recipient = Self::fee_recipient(&index);
+        Self::_mint(id, recipient.clone(), accrued)?;
+        <UnitMultiplier<T>>::insert(&id, multiplier);
+        <LastFeeAccrual<T>>::insert(&id, now);
+        Self::deposit_event(RawEvent::StreamingFeeAccrued(id, recipient, accrued));
+        Ok(())
+    }
+
+    /// The account credited with the fees of `index`.
+    pub fn fee_recipient(index: &StoneIndex<T::IndexId, T::AssetId, T::AccountId>) -> T::AccountId {
+        T::FeeRecipient::get().unwrap_or_else(|| index.owner.clone())
+    }
+
+    /// The account holding the underlying assets which back the index `id`.
+    pub fn vault_account(id: T::IndexId) -> T::AccountId {
+        T::ModuleId::get().into_sub_account(id)
+    }
+
+    /// The share of the component weights backing one unit of index `id`.
+    pub fn unit_multiplier(id: T::IndexId) -> FixedU128 {
+        <UnitMultiplier<T>>::get(id).unwrap_or_else(|| FixedU128::saturating_from_integer(1u32))
+    }
+
+    /// The amount of a component asset backing `amount` smallest units of index `id`. Every
+    /// step is rounded the same way, so the vault never loses to rounding.
+    pub fn component_amount(
+        id: T::IndexId,
+        amount: T::Balance,
+        comp: &StoneIndexComponent<T::AssetId>,
+        rounding: Rounding,
+    ) -> Result<T::Balance, DispatchError> {
+        let accuracy = FixedU128::accuracy();
+        let one_unit = 10u128.pow(Self::indexes(&id).decimals.min(MAX_DECIMALS).into());
+
+        // The amount backing one whole unit after dilution, in the fixed-point accuracy.
+        let multiplier = Self::unit_multiplier(id).into_inner();
+        let per_unit = Self::mul_div(comp.weight.into_inner(), multiplier, accuracy, rounding)?;
+        let scaled = Self::mul_div(amount.saturated_into(), per_unit, accuracy, rounding)?;
+        let value = Self::mul_div(scaled, 1, one_unit, rounding)?;
+        value.try_into().map_err(|_| Error::<T>::Overflow.into())
+    }
+
+    // `a * b / c`, rounded as asked.
+    fn mul_div(a: u128, b: u128, c: u128, rounding: Rounding) -> Result<u128, DispatchError> {
+        ensure!(c != 0, Error::<T>::Overflow);
+        let floor = match a.checked_mul(b) {
+            Some(product) => product / c,
+            None => {
+                // Past `u128` the quotient is rounded to the nearest integer, so it may be one too high.
+                let quotient = multiply_by_rational(a, b, c).map_err(|_| Error::<T>::Overflow)?;
+                if mul_wide(quotient, c) > mul_wide(a, b) { quotient - 1 } else { quotient }
+            }
+        };
+        if rounding == Rounding::Up && mul_wide(floor, c) != mul_wide(a, b) {
+            return floor.checked_add(1).ok_or_else(|| Error::<T>::Overflow.into());
+        }
+        Ok(floor)
+    }
+
+    /// The net asset value of one whole unit of index `id`, in the currency prices are quoted in.
+    pub fn nav(id: T::IndexId) -> Result<Price, DispatchError> {
+        ensure!(<Indexes<T>>::contains_key(&id), Error::<T>::IndexNotExist);
+        Self::composition_value(&Self::indexes(&id).components)?
+            .checked_mul(&Self::unit_multiplier(id))
+            .ok_or_else(|| Error::<T>::Overflow.into())
+    }
+
+    // The value of `weight` of every component, i.e. of one undiluted whole index unit.
+    fn composition_value(components: &[StoneIndexComponent<T::AssetId>]) -> Result<Price, DispatchError> {
+        let zero = <Price as FixedPointNumber>::zero();
+        components.iter().try_fold(zero, |total: Price, comp| -> Result<Price, DispatchError> {
+            let price = Self::component_price(comp.asset_id)?;
+            let value = comp.weight
+                .checked_mul(&price)
+                .and_then(|value| total.checked_add(&value))
+                .ok_or(Error::<T>::Overflow)?;
+            Ok(value)
+        })
+    }
+
+    /// The value of the components held by the vault of index `id`.
+    pub fn aum(id: T::IndexId) -> Result<Price, DispatchError> {
+        ensure!(<Indexes<T>>::contains_key(&id), Error::<T>::IndexNotExist);
+        let vault = Self::vault_account(id);
+
+        let zero = <Price as FixedPointNumber>::zero();
+        Self::indexes(&id).components.iter().try_fold(zero, |aum: Price, comp| -> Result<Price, DispatchError> {
+            let price = Self::component_price(comp.asset_id)?;
+            let held: u128 = pallet_assets::Module::<T>::balance(comp.asset_id, vault.clone()).saturated_into();
+            let value = Price::checked_from_integer(held)
+                .and_then(|held| held.checked_mul(&price))
+                .and_then(|value| aum.checked_add(&value))
+                .ok_or(Error::<T>::Overflow)?;
+            Ok(value)
+        })
+    }
+
+    fn component_price(asset_id: T::AssetId) -> Result<Price, DispatchError> {
+        T::PriceProvider::price(asset_id)
+            .filter(|price| !price.is_zero())
+            .ok_or_else(|| Error::<T>::PriceUnavailable.into())
+    }
+
+    /// The asset representing the units of index `id`, if the index exists.
+    pub fn index_asset(id: T::IndexId) -> Option<T::AssetId> {
+        <Indexes<T>>::try_get(&id).ok().map(|index| index.asset_id)
+    }
+
+    /// The number of units of index `id` held by `who`.
+    pub fn index_balance(id: T::IndexId, who: &T::AccountId) -> T::Balance {
+        Self::index_asset(id)
+            .map(|asset_id| pallet_assets::Module::<T>::balance(asset_id, who.clone()))
+            .unwrap_or_else(Zero::zero)
+    }
+
+    /// The total number of units of index `id` held by all accounts.
+    pub fn index_total_supply(id: T::IndexId) -> T::Balance {
+        Self::index_asset(id)
+            .map(pallet_assets::Module::<T>::total_supply)
+            .unwrap_or_else(Zero::zero)
+    }
+
+    fn set_allowance(id: T::IndexId, owner: T::AccountId, spender: T::AccountId, amount: T::Balance) {
+        if amount.is_zero() {
+            <Allowances<T>>::remove(id, (&owner, &spender));
+        } else {
+            <Allowances<T>>::insert(id, (&owner, &spender), amount);
+        }
+        Self::deposit_event(RawEvent::Approval(id, owner, spender, amount));
+    }
+
+    pub fn _mint(index_id: T::IndexId, account: T::AccountId, amount: T::Balance) -> DispatchResult {
+        let asset_id = Self::index_asset(index_id).ok_or(Error::<T>::IndexNotExist)?;
+        pallet_assets::Module::<T>::mint(asset_id, account, amount)
+    }
+
+    /// Mint `amount` units of index `id` to `who` together with the components backing them,
+    /// which are minted straight into the vault.
+    fn mint_backed(id: T::IndexId, who: T::AccountId, amount: T::Balance) -> DispatchResult {
+        ensure!(<Indexes<T>>::contains_key(&id), Error::<T>::IndexNotExist);
+        let vault = Self::vault_account(id);
+        for comp in Self::indexes(&id).components.iter() {
+            let comp_value = Self::component_amount(id, amount, comp, Rounding::Up)?;
+            pallet_assets::Module::<T>::mint(comp.asset_id, vault.clone(), comp_value)?;
+        }
+        Self::_mint(id, who, amount)
+    }
+
+    pub fn _burn(index_id: T::IndexId, account: T::AccountId, amount: T::Balance) -> DispatchResult {
+        let asset_id = Self::index_asset(index_id).ok_or(Error::<T>::IndexNotExist)?;
+        pallet_assets::Module::<T>::burn(asset_id, account, amount)
+    }
+
+    // Moving units between holders leaves the total supply untouched.
+    pub fn _transfer(index_id: T::IndexId, from: T::AccountId, to: T::AccountId, amount: T::Balance) -> DispatchResult {
+        let asset_id = Self::index_asset(index_id).ok_or(Error::<T>::IndexNotExist)?;
+        pallet_assets::Module::<T>::make_transfer(asset_id, from, to, amount)
+    }
+}
+
+// The full 256 bit product of `x` and `y`, as its high and low halves.
+fn mul_wide(x: u128, y: u128) -> (u128, u128) {
+    let (x_high, x_low) = (x >> 64, x & u128::from(u64::MAX));
+    let (y_high, y_low) = (y >> 64, y & u128::from(u64::MAX));
+    let (middle, carry) = (x_high * y_low).overflowing_add(x_low * y_high);
+    let (low, low_carry) = (x_low * y_low).overflowing_add(middle << 64);
+    let high = x_high * y_high + (middle >> 64) + (u128::from(carry) << 64) + u128::from(low_carry);
+    (high, low)
+}
+
+// Units of an index move through `pallet_assets` too, so a frozen index has to be enforced there.
+impl<T: Config> pallet_assets::TransferGuard<T::AssetId, T::AccountId> for Module<T> {
+    fn ensure_can_transfer(asset_id: T::AssetId, _from: &T::AccountId, _to: &T::AccountId) -> DispatchResult {
+        if let Some(id) = Self::asset_index(asset_id) {
+            ensure!(Self::indexes(&id).status.allows_transfer(), Error::<T>::IndexPaused);
+        }
+        Ok(())
     }
 }
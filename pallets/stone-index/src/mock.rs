@@ -1,11 +1,15 @@
 use crate as pallet_stone_index;
-use pallet_stone_index::{StoneIndex, StoneIndexComponent, Config};
-use frame_support::{parameter_types, construct_runtime};
+use pallet_stone_index::{StoneIndex, StoneIndexComponent, Config, Swap};
+use frame_support::{parameter_types, construct_runtime, traits::{Contains, OnInitialize}};
+use frame_system::{EnsureRoot, EnsureSignedBy};
+use pallet_oracle::{Price, PriceProvider};
 use sp_core::H256;
 use sp_runtime::{
+	DispatchError, FixedPointNumber, FixedU128, ModuleId, Perbill, Permill,
 	testing::Header,
 	traits::{BlakeTwo256, IdentityLookup},
 };
+use std::{cell::RefCell, collections::BTreeMap};
 
 type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<TestRuntime>;
 type Block = frame_system::mocking::MockBlock<TestRuntime>;
@@ -18,6 +22,7 @@ construct_runtime!(
 		UncheckedExtrinsic = UncheckedExtrinsic,
 	{
 		System: frame_system::{Module, Call, Config, Storage, Event<T>},
+		Balances: pallet_balances::{Module, Call, Storage, Config<T>, Event<T>},
 		Assets: pallet_assets::{Module, Call, Event<T>},
 		StoneIndexPallet: pallet_stone_index::{Module, Call, Storage, Event<T>},
 	}
@@ -38,7 +43,8 @@ impl frame_system::Config for TestRuntime {
 	type BlockNumber = u64;
 	type Hash = H256;
 	type Hashing = BlakeTwo256;
-	type AccountId = u64;
+	// Wide enough for every per-index vault sub-account to be distinct.
+	type AccountId = u128;
 	type Lookup = IdentityLookup<Self::AccountId>;
 	type Header = Header;
 	type Event = ();
@@ -46,33 +52,155 @@ impl frame_system::Config for TestRuntime {
 	type DbWeight = ();
 	type Version = ();
 	type PalletInfo = PalletInfo;
-	type AccountData = ();
+	type AccountData = pallet_balances::AccountData<u64>;
 	type OnNewAccount = ();
 	type OnKilledAccount = ();
 	type SystemWeightInfo = ();
 	type SS58Prefix = SS58Prefix;
 }
 
+parameter_types! {
+	pub const ExistentialDeposit: u64 = 1;
+	pub const MaxLocks: u32 = 50;
+}
+
+impl pallet_balances::Config for TestRuntime {
+	type MaxLocks = MaxLocks;
+	type Balance = u64;
+	type DustRemoval = ();
+	type Event = ();
+	type ExistentialDeposit = ExistentialDeposit;
+	type AccountStore = System;
+	type WeightInfo = ();
+}
+
 impl pallet_assets::Config for TestRuntime {
 	type Event = ();
-	type Balance = u64;
+	type Balance = u128;
 	type AssetId = u32;
+	type TransferGuard = StoneIndexPallet;
 }
 
 parameter_types! {
-	pub const CustodialAccount: u64 = 250;
+	pub const StoneIndexModuleId: ModuleId = ModuleId(*b"stone/ix");
+	pub const MaxComponents: u32 = 4;
+	pub const MaxNameLength: u32 = 16;
+	pub const UpdateDelay: u64 = 10;
+	pub const MaxFee: Permill = Permill::from_percent(5);
+	pub const FeeRecipient: Option<u128> = None;
+	pub const BlocksPerYear: u64 = 1000;
+	pub const MaxStreamingFee: Perbill = Perbill::from_percent(20);
+	pub InitialUnitValue: Price = Price::saturating_from_integer(100);
+	pub const MetadataDepositBase: u64 = 10;
+	pub const MetadataDepositPerByte: u64 = 1;
+	pub const MaxSymbolLength: u32 = 8;
+	pub const MaxDescriptionLength: u32 = 32;
+}
+
+// Only the test account may create indexes.
+pub struct IndexCreators;
+impl Contains<u128> for IndexCreators {
+	fn sorted_members() -> Vec<u128> {
+		vec![TEST_ACCOUNT_ID]
+	}
+}
+
+// Swaps any asset for any other one to one, less a flat fee of one unit per swap.
+pub struct FlatFeeSwap;
+impl Swap<u128, u32, u128> for FlatFeeSwap {
+	fn swap_exact_out(
+		who: &u128,
+		asset_in: u32,
+		asset_out: u32,
+		amount_out: u128,
+		max_amount_in: u128,
+	) -> Result<u128, DispatchError> {
+		let amount_in = amount_out + 1;
+		if amount_in > max_amount_in {
+			return Err(DispatchError::Other("FlatFeeSwap: max_amount_in exceeded"));
+		}
+		Assets::burn(asset_in, *who, amount_in)?;
+		Assets::mint(asset_out, *who, amount_out)?;
+		Ok(amount_in)
+	}
+
+	fn swap_exact_in(
+		who: &u128,
+		asset_in: u32,
+		asset_out: u32,
+		amount_in: u128,
+		min_amount_out: u128,
+	) -> Result<u128, DispatchError> {
+		let amount_out = amount_in.saturating_sub(1);
+		if amount_out < min_amount_out {
+			return Err(DispatchError::Other("FlatFeeSwap: min_amount_out not reached"));
+		}
+		Assets::burn(asset_in, *who, amount_in)?;
+		Assets::mint(asset_out, *who, amount_out)?;
+		Ok(amount_out)
+	}
+}
+
+thread_local! {
+	static PRICES: RefCell<BTreeMap<u32, Price>> = RefCell::new(BTreeMap::new());
+}
+
+// Quotes the prices set through `set_price`; every other asset has no fresh price.
+pub struct TestPrices;
+impl PriceProvider<u32> for TestPrices {
+	fn price(asset_id: u32) -> Option<Price> {
+		PRICES.with(|prices| prices.borrow().get(&asset_id).copied())
+	}
+}
+
+pub fn set_price(asset_id: u32, price: Option<Price>) {
+	PRICES.with(|prices| match price {
+		Some(price) => prices.borrow_mut().insert(asset_id, price),
+		None => prices.borrow_mut().remove(&asset_id),
+	});
 }
 
 impl Config for TestRuntime {
 	type Event = ();
 	type IndexId = u32;
+	type ModuleId = StoneIndexModuleId;
+	type MaxComponents = MaxComponents;
+	type MaxNameLength = MaxNameLength;
+	type UpdateDelay = UpdateDelay;
+	type CreateOrigin = EnsureSignedBy<IndexCreators, u128>;
+	type AdminOrigin = EnsureRoot<u128>;
+	type FeeRecipient = FeeRecipient;
+	type MaxFee = MaxFee;
+	type BlocksPerYear = BlocksPerYear;
+	type MaxStreamingFee = MaxStreamingFee;
+	type Swap = FlatFeeSwap;
+	type PriceProvider = TestPrices;
+	type InitialUnitValue = InitialUnitValue;
+	type Currency = Balances;
+	type MetadataDepositBase = MetadataDepositBase;
+	type MetadataDepositPerByte = MetadataDepositPerByte;
+	type MaxSymbolLength = MaxSymbolLength;
+	type MaxDescriptionLength = MaxDescriptionLength;
 }
 
 pub const TEST_INDEX_ID: u32 = 1;
-pub const TEST_ACCOUNT_ID: u64 = 99999;
+pub const TEST_ACCOUNT_ID: u128 = 99999;
+
+// Advance the chain to block `n`, running the pallet's block initialization on the way.
+pub fn run_to_block(n: u64) {
+	while System::block_number() < n {
+		System::set_block_number(System::block_number() + 1);
+		StoneIndexPallet::on_initialize(System::block_number());
+	}
+}
 
 // Build genesis storage according to the mock runtime.
 pub fn new_test_ext() -> sp_io::TestExternalities {
+	new_test_ext_with_balances(vec![])
+}
+
+// Build genesis storage with some index units already credited to holders and backed in the vault.
+pub fn new_test_ext_with_balances(balances: Vec<(u32, u128, u128)>) -> sp_io::TestExternalities {
 	let config: pallet_stone_index::GenesisConfig<TestRuntime> = pallet_stone_index::GenesisConfig {
 		indexes: vec![(
 			TEST_INDEX_ID,
@@ -82,16 +210,30 @@ pub fn new_test_ext() -> sp_io::TestExternalities {
 				components: vec![
 					StoneIndexComponent {
 						asset_id: 10001,
-						weight: 2,
+						weight: FixedU128::saturating_from_integer(2),
+						..Default::default()
 					},
 					StoneIndexComponent {
 						asset_id: 10002,
-						weight: 1,
+						weight: FixedU128::saturating_from_integer(1),
+						..Default::default()
 					},
 				],
 				owner: TEST_ACCOUNT_ID,
+				..Default::default()
 			},
-		)]
+		)],
+		balances,
 	};
-	config.build_storage().unwrap().into()
+	let mut storage = config.build_storage().unwrap();
+	// Metadata deposits are reserved from the test account and account 2.
+	pallet_balances::GenesisConfig::<TestRuntime> {
+		balances: vec![(TEST_ACCOUNT_ID, 100), (2, 100)],
+	}
+	.assimilate_storage(&mut storage)
+	.unwrap();
+	let mut ext: sp_io::TestExternalities = storage.into();
+	ext.execute_with(|| System::set_block_number(1));
+	PRICES.with(|prices| prices.borrow_mut().clear());
+	ext
 }
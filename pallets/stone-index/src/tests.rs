@@ -1,69 +1,577 @@
-use crate::{mock::*, Error, StoneIndex, StoneIndexComponent};
+use crate::{mock::*, CompositionMode, Error, IndexStatus, Rounding, StoneIndex, StoneIndexComponent};
 use frame_support::{assert_noop, assert_ok, debug};
+use pallet_oracle::Price;
+use sp_runtime::{DispatchError, FixedPointNumber, FixedU128, Perbill, Permill};
+
+// Every account the tests credit with index units.
+fn sum_of_holder_balances(index_id: u32) -> u128 {
+	[TEST_ACCOUNT_ID, 2, 3]
+		.iter()
+		.map(|who| StoneIndexPallet::index_balance(index_id, who))
+		.sum()
+}
+
+// The components of the genesis index only exist once something has been minted.
+fn issue_test_assets() {
+	assert_ok!(Assets::mint(10001, TEST_ACCOUNT_ID, 10000));
+	assert_ok!(Assets::mint(10002, TEST_ACCOUNT_ID, 100));
+}
+
+fn component(asset_id: u32, weight: u32) -> StoneIndexComponent<u32> {
+	StoneIndexComponent { asset_id, weight: FixedU128::saturating_from_integer(weight), ..Default::default() }
+}
 
 #[test]
 fn add_index() {
 	new_test_ext().execute_with(|| {
+		issue_test_assets();
 		let test_index = StoneIndex {
-			id: 100,
+			id: 2,
 			name: "test".as_bytes().to_vec(),
 			components: vec![
 				StoneIndexComponent {
 					asset_id: 10001,
-					weight: 1,
+					weight: FixedU128::saturating_from_integer(1),
+					..Default::default()
 				},
 				StoneIndexComponent {
 					asset_id: 10002,
-					weight: 5,
+					weight: FixedU128::saturating_from_integer(5),
+					..Default::default()
 				},
 			],
 			owner: TEST_ACCOUNT_ID,
+			// The genesis index took the first asset id.
+			asset_id: 1,
+			..Default::default()
 		};
+		// The genesis index takes id 1, so the next one is 2.
+		assert_eq!(StoneIndexPallet::next_index_id(), test_index.id);
 		// Dispatch a signed extrinsic.
 		assert_ok!(StoneIndexPallet::add_index(
 			Origin::signed(TEST_ACCOUNT_ID),
-			test_index.id,
-			test_index.name,
-			test_index.components
+			test_index.name.clone(),
+			test_index.components.clone(),
+			CompositionMode::Quantity,
+			0
 		));
 		let out_index = StoneIndexPallet::get_index(&test_index.id);
-		assert_eq!(out_index.id, 100);
+		assert_eq!(out_index, test_index);
 		assert_eq!(std::str::from_utf8(&out_index.name).unwrap(), "test");
+		assert_eq!(StoneIndexPallet::next_index_id(), 3);
 		debug::info!("The index is {:?}", out_index);
 	});
 }
 
+#[test]
+fn add_index_cannot_overwrite_existing_index() {
+	new_test_ext().execute_with(|| {
+		issue_test_assets();
+		let original = StoneIndexPallet::indexes(TEST_INDEX_ID);
+		assert_ok!(StoneIndexPallet::add_index(
+			Origin::signed(TEST_ACCOUNT_ID),
+			"mine".as_bytes().to_vec(),
+			original.components.clone(),
+			CompositionMode::Quantity,
+			0
+		));
+		assert_eq!(StoneIndexPallet::indexes(TEST_INDEX_ID), original);
+		assert_eq!(StoneIndexPallet::indexes(2).name, b"mine".to_vec());
+	});
+}
+
+#[test]
+fn add_index_requires_create_origin() {
+	new_test_ext().execute_with(|| {
+		issue_test_assets();
+		assert_noop!(
+			StoneIndexPallet::add_index(
+				Origin::signed(123456),
+				b"nope".to_vec(),
+				vec![component(10001, 1)],
+				CompositionMode::Quantity,
+				0
+			),
+			sp_runtime::DispatchError::BadOrigin
+		);
+		assert_noop!(
+			StoneIndexPallet::add_index(
+				Origin::root(),
+				b"nope".to_vec(),
+				vec![component(10001, 1)],
+				CompositionMode::Quantity,
+				0
+			),
+			sp_runtime::DispatchError::BadOrigin
+		);
+	});
+}
+
+#[test]
+fn force_add_index() {
+	new_test_ext().execute_with(|| {
+		issue_test_assets();
+		let components = StoneIndexPallet::indexes(TEST_INDEX_ID).components;
+		assert_noop!(
+			StoneIndexPallet::force_add_index(
+				Origin::signed(TEST_ACCOUNT_ID),
+				10,
+				"forced".as_bytes().to_vec(),
+				components.clone(),
+				CompositionMode::Quantity,
+				0,
+				TEST_ACCOUNT_ID
+			),
+			sp_runtime::DispatchError::BadOrigin
+		);
+		assert_noop!(
+			StoneIndexPallet::force_add_index(
+				Origin::root(),
+				TEST_INDEX_ID,
+				"forced".as_bytes().to_vec(),
+				components.clone(),
+				CompositionMode::Quantity,
+				0,
+				123456
+			),
+			Error::<TestRuntime>::IndexAlreadyExists
+		);
+		assert_ok!(StoneIndexPallet::force_add_index(
+			Origin::root(),
+			10,
+			"forced".as_bytes().to_vec(),
+			components.clone(),
+			CompositionMode::Quantity,
+			0,
+			123456
+		));
+		assert_eq!(StoneIndexPallet::indexes(10).owner, 123456);
+		// Automatically assigned ids skip past the forced one.
+		assert_eq!(StoneIndexPallet::next_index_id(), 11);
+	});
+}
+
 #[test]
 fn update_index() {
 	new_test_ext().execute_with(|| {
+		issue_test_assets();
 		let test_index = StoneIndexPallet::indexes(TEST_INDEX_ID);
 
-		assert_noop!(StoneIndexPallet::update_index(
+		assert_noop!(StoneIndexPallet::propose_update(
 			Origin::signed(123456),
 			test_index.id,
 			"Another Name".as_bytes().to_vec(),
 			test_index.components.clone()
-		), Error::<TestRuntime>::NotTheOwner);
+		), Error::<TestRuntime>::NotTheOperator);
 
-		assert_ok!(StoneIndexPallet::update_index(
+		assert_ok!(StoneIndexPallet::propose_update(
 			Origin::signed(TEST_ACCOUNT_ID),
 			test_index.id,
 			"Another Name".as_bytes().to_vec(),
 			test_index.components.clone()
 		));
+		assert_eq!(StoneIndexPallet::pending_updates(test_index.id).unwrap().enact_at, 11);
 
+		// Nothing changes until the timelock expires.
+		run_to_block(10);
+		assert_eq!(StoneIndexPallet::get_index(&test_index.id), test_index);
+
+		run_to_block(11);
 		let out_index = StoneIndexPallet::get_index(&test_index.id);
 		assert_eq!(out_index.id, 1);
 		assert_eq!(std::str::from_utf8(&out_index.name).unwrap(), "Another Name");
+		assert_eq!(StoneIndexPallet::pending_updates(test_index.id), None);
 		debug::info!("The index is {:?}", out_index);
 	});
 }
 
+#[test]
+fn only_one_update_can_be_pending() {
+	new_test_ext().execute_with(|| {
+		issue_test_assets();
+		assert_ok!(StoneIndexPallet::propose_update(
+			Origin::signed(TEST_ACCOUNT_ID),
+			TEST_INDEX_ID,
+			b"First".to_vec(),
+			vec![component(10001, 1)]
+		));
+		assert_noop!(
+			StoneIndexPallet::propose_update(
+				Origin::signed(TEST_ACCOUNT_ID),
+				TEST_INDEX_ID,
+				b"Second".to_vec(),
+				vec![component(10002, 1)]
+			),
+			Error::<TestRuntime>::UpdateAlreadyPending
+		);
+	});
+}
+
+#[test]
+fn cancel_update() {
+	new_test_ext().execute_with(|| {
+		issue_test_assets();
+		let original = StoneIndexPallet::indexes(TEST_INDEX_ID);
+		assert_noop!(
+			StoneIndexPallet::cancel_update(Origin::signed(TEST_ACCOUNT_ID), TEST_INDEX_ID),
+			Error::<TestRuntime>::NoPendingUpdate
+		);
+		assert_ok!(StoneIndexPallet::propose_update(
+			Origin::signed(TEST_ACCOUNT_ID),
+			TEST_INDEX_ID,
+			b"Cancelled".to_vec(),
+			vec![component(10001, 1)]
+		));
+		assert_noop!(
+			StoneIndexPallet::cancel_update(Origin::signed(123456), TEST_INDEX_ID),
+			Error::<TestRuntime>::NotTheOperator
+		);
+		assert_ok!(StoneIndexPallet::cancel_update(Origin::signed(TEST_ACCOUNT_ID), TEST_INDEX_ID));
+		assert_eq!(StoneIndexPallet::pending_updates(TEST_INDEX_ID), None);
+		assert!(StoneIndexPallet::update_schedule(11).is_empty());
+
+		run_to_block(20);
+		assert_eq!(StoneIndexPallet::indexes(TEST_INDEX_ID), original);
+	});
+}
+
+#[test]
+fn enact_update_waits_for_timelock() {
+	new_test_ext().execute_with(|| {
+		issue_test_assets();
+		assert_ok!(StoneIndexPallet::propose_update(
+			Origin::signed(TEST_ACCOUNT_ID),
+			TEST_INDEX_ID,
+			b"Early".to_vec(),
+			vec![component(10001, 1)]
+		));
+		run_to_block(5);
+		assert_noop!(
+			StoneIndexPallet::enact_update(Origin::signed(TEST_ACCOUNT_ID), TEST_INDEX_ID),
+			Error::<TestRuntime>::UpdateNotDue
+		);
+	});
+}
+
+#[test]
+fn holders_can_exit_before_update_is_enacted() {
+	new_test_ext().execute_with(|| {
+		let holder = 2;
+		assert_ok!(Assets::mint(10001, holder, 10));
+		assert_ok!(Assets::mint(10002, holder, 5));
+		assert_ok!(StoneIndexPallet::buy_index(Origin::signed(holder), TEST_INDEX_ID, 5));
+		assert_ok!(StoneIndexPallet::propose_update(
+			Origin::signed(TEST_ACCOUNT_ID),
+			TEST_INDEX_ID,
+			b"FirstIndex".to_vec(),
+			vec![component(10001, 1)]
+		));
+
+		run_to_block(3);
+		assert_ok!(StoneIndexPallet::sell_index(Origin::signed(holder), TEST_INDEX_ID, 5));
+		assert_eq!(Assets::balance(10001, holder), 10);
+		assert_eq!(Assets::balance(10002, holder), 5);
+
+		// With no supply left, the update needs no settlement.
+		run_to_block(11);
+		assert_eq!(StoneIndexPallet::indexes(TEST_INDEX_ID).components, vec![component(10001, 1)]);
+	});
+}
+
+#[test]
+fn force_update_skips_timelock() {
+	new_test_ext().execute_with(|| {
+		issue_test_assets();
+		assert_noop!(
+			StoneIndexPallet::force_update(
+				Origin::signed(TEST_ACCOUNT_ID),
+				TEST_INDEX_ID,
+				b"Forced".to_vec(),
+				vec![component(10001, 3)]
+			),
+			sp_runtime::DispatchError::BadOrigin
+		);
+		assert_ok!(StoneIndexPallet::force_update(
+			Origin::root(),
+			TEST_INDEX_ID,
+			b"Forced".to_vec(),
+			vec![component(10001, 3)]
+		));
+		let index = StoneIndexPallet::indexes(TEST_INDEX_ID);
+		assert_eq!(index.name, b"Forced".to_vec());
+		assert_eq!(index.components, vec![component(10001, 3)]);
+	});
+}
+
+#[test]
+fn force_transfer_ownership() {
+	new_test_ext().execute_with(|| {
+		issue_test_assets();
+		assert_noop!(
+			StoneIndexPallet::force_transfer_ownership(Origin::signed(TEST_ACCOUNT_ID), TEST_INDEX_ID, 2),
+			sp_runtime::DispatchError::BadOrigin
+		);
+		assert_noop!(
+			StoneIndexPallet::force_transfer_ownership(Origin::root(), 999, 2),
+			Error::<TestRuntime>::IndexNotExist
+		);
+		assert_ok!(StoneIndexPallet::force_transfer_ownership(Origin::root(), TEST_INDEX_ID, 2));
+		assert_eq!(StoneIndexPallet::indexes(TEST_INDEX_ID).owner, 2);
+
+		// The new owner manages the index from now on.
+		assert_noop!(
+			StoneIndexPallet::propose_update(
+				Origin::signed(TEST_ACCOUNT_ID),
+				TEST_INDEX_ID,
+				b"Old owner".to_vec(),
+				vec![component(10001, 1)]
+			),
+			Error::<TestRuntime>::NotTheOperator
+		);
+		assert_ok!(StoneIndexPallet::propose_update(
+			Origin::signed(2),
+			TEST_INDEX_ID,
+			b"New owner".to_vec(),
+			vec![component(10001, 1)]
+		));
+	});
+}
+
+#[test]
+fn composition_must_not_be_empty() {
+	new_test_ext().execute_with(|| {
+		issue_test_assets();
+		assert_noop!(
+			StoneIndexPallet::add_index(
+				Origin::signed(TEST_ACCOUNT_ID),
+				b"empty".to_vec(),
+				vec![],
+				CompositionMode::Quantity,
+				0
+			),
+			Error::<TestRuntime>::EmptyComposition
+		);
+	});
+}
+
+#[test]
+fn component_weights_must_be_non_zero() {
+	new_test_ext().execute_with(|| {
+		issue_test_assets();
+		assert_noop!(
+			StoneIndexPallet::add_index(
+				Origin::signed(TEST_ACCOUNT_ID),
+				b"zero".to_vec(),
+				vec![component(10001, 1), component(10002, 0)],
+				CompositionMode::Quantity,
+				0
+			),
+			Error::<TestRuntime>::ZeroWeight
+		);
+	});
+}
+
+#[test]
+fn components_must_be_distinct() {
+	new_test_ext().execute_with(|| {
+		issue_test_assets();
+		assert_noop!(
+			StoneIndexPallet::add_index(
+				Origin::signed(TEST_ACCOUNT_ID),
+				b"dup".to_vec(),
+				vec![component(10001, 1), component(10002, 1), component(10001, 3)],
+				CompositionMode::Quantity,
+				0
+			),
+			Error::<TestRuntime>::DuplicateComponent
+		);
+	});
+}
+
+#[test]
+fn components_must_be_issued_assets() {
+	new_test_ext().execute_with(|| {
+		issue_test_assets();
+		assert_noop!(
+			StoneIndexPallet::add_index(
+				Origin::signed(TEST_ACCOUNT_ID),
+				b"unknown".to_vec(),
+				vec![component(10001, 1), component(424242, 1)],
+				CompositionMode::Quantity,
+				0
+			),
+			Error::<TestRuntime>::UnknownAsset
+		);
+	});
+}
+
+#[test]
+fn composition_size_is_bounded() {
+	new_test_ext().execute_with(|| {
+		// Keep clear of the ids `add_index` hands out to new index assets.
+		for asset_id in 101..=105 {
+			assert_ok!(Assets::mint(asset_id, TEST_ACCOUNT_ID, 1));
+		}
+		let components: Vec<_> = (101..=105).map(|asset_id| component(asset_id, 1)).collect();
+		assert_noop!(
+			StoneIndexPallet::add_index(
+				Origin::signed(TEST_ACCOUNT_ID),
+				b"big".to_vec(),
+				components.clone(),
+				CompositionMode::Quantity,
+				0
+			),
+			Error::<TestRuntime>::TooManyComponents
+		);
+		assert_ok!(StoneIndexPallet::add_index(
+			Origin::signed(TEST_ACCOUNT_ID),
+			b"max".to_vec(),
+			components[..4].to_vec(),
+			CompositionMode::Quantity,
+			0
+		));
+	});
+}
+
+#[test]
+fn name_length_is_bounded() {
+	new_test_ext().execute_with(|| {
+		issue_test_assets();
+		let components = vec![component(10001, 1)];
+		assert_noop!(
+			StoneIndexPallet::add_index(
+				Origin::signed(TEST_ACCOUNT_ID),
+				vec![b'x'; 17],
+				components.clone(),
+				CompositionMode::Quantity,
+				0
+			),
+			Error::<TestRuntime>::NameTooLong
+		);
+		assert_ok!(StoneIndexPallet::add_index(
+			Origin::signed(TEST_ACCOUNT_ID),
+			vec![b'x'; 16],
+			components,
+			CompositionMode::Quantity,
+			0
+		));
+	});
+}
+
+#[test]
+fn update_index_is_validated() {
+	new_test_ext().execute_with(|| {
+		issue_test_assets();
+		assert_noop!(
+			StoneIndexPallet::propose_update(
+				Origin::signed(TEST_ACCOUNT_ID),
+				TEST_INDEX_ID,
+				b"FirstIndex".to_vec(),
+				vec![component(10001, 1), component(10001, 1)]
+			),
+			Error::<TestRuntime>::DuplicateComponent
+		);
+		assert_noop!(
+			StoneIndexPallet::propose_update(
+				Origin::signed(TEST_ACCOUNT_ID),
+				TEST_INDEX_ID,
+				b"FirstIndex".to_vec(),
+				vec![]
+			),
+			Error::<TestRuntime>::EmptyComposition
+		);
+	});
+}
+
+#[test]
+fn update_index_rebalances_vault() {
+	new_test_ext().execute_with(|| {
+		let holder = 2;
+		let vault = StoneIndexPallet::vault_account(TEST_INDEX_ID);
+		assert_ok!(Assets::mint(10001, holder, 10));
+		assert_ok!(Assets::mint(10002, holder, 5));
+		assert_ok!(StoneIndexPallet::buy_index(Origin::signed(holder), TEST_INDEX_ID, 5));
+		assert_ok!(Assets::mint(10002, TEST_ACCOUNT_ID, 5));
+		assert_ok!(Assets::mint(10003, TEST_ACCOUNT_ID, 5));
+
+		// From 2 x 10001 + 1 x 10002 to 1 x 10001 + 2 x 10002 + 1 x 10003 per unit.
+		assert_ok!(StoneIndexPallet::propose_update(
+			Origin::signed(TEST_ACCOUNT_ID),
+			TEST_INDEX_ID,
+			b"FirstIndex".to_vec(),
+			vec![component(10001, 1), component(10002, 2), component(10003, 1)]
+		));
+		run_to_block(11);
+		assert_eq!(Assets::balance(10001, vault), 5);
+		assert_eq!(Assets::balance(10002, vault), 10);
+		assert_eq!(Assets::balance(10003, vault), 5);
+		assert_eq!(Assets::balance(10001, TEST_ACCOUNT_ID), 5);
+		assert_eq!(Assets::balance(10002, TEST_ACCOUNT_ID), 0);
+		assert_eq!(Assets::balance(10003, TEST_ACCOUNT_ID), 0);
+
+		// The holder redeems under the new composition and the vault is left empty.
+		assert_ok!(StoneIndexPallet::sell_index(Origin::signed(holder), TEST_INDEX_ID, 5));
+		assert_eq!(Assets::balance(10001, holder), 5);
+		assert_eq!(Assets::balance(10002, holder), 10);
+		assert_eq!(Assets::balance(10003, holder), 5);
+		assert_eq!(Assets::balance(10001, vault), 0);
+		assert_eq!(Assets::balance(10002, vault), 0);
+		assert_eq!(Assets::balance(10003, vault), 0);
+	});
+}
+
+#[test]
+fn update_stays_pending_when_owner_cannot_cover_rebalance() {
+	new_test_ext().execute_with(|| {
+		let holder = 2;
+		assert_ok!(Assets::mint(10001, holder, 10));
+		assert_ok!(Assets::mint(10002, holder, 5));
+		assert_ok!(StoneIndexPallet::buy_index(Origin::signed(holder), TEST_INDEX_ID, 5));
+		assert_ok!(Assets::mint(10003, TEST_ACCOUNT_ID, 4));
+		let original = StoneIndexPallet::indexes(TEST_INDEX_ID);
+		let new_components = vec![component(10001, 2), component(10002, 1), component(10003, 1)];
+
+		assert_ok!(StoneIndexPallet::propose_update(
+			Origin::signed(TEST_ACCOUNT_ID),
+			TEST_INDEX_ID,
+			b"FirstIndex".to_vec(),
+			new_components.clone()
+		));
+		run_to_block(11);
+		assert_eq!(StoneIndexPallet::indexes(TEST_INDEX_ID), original);
+		assert!(StoneIndexPallet::pending_updates(TEST_INDEX_ID).is_some());
+		assert_noop!(
+			StoneIndexPallet::enact_update(Origin::signed(TEST_ACCOUNT_ID), TEST_INDEX_ID),
+			Error::<TestRuntime>::InsufficientAssetBalance
+		);
+
+		assert_ok!(Assets::mint(10003, TEST_ACCOUNT_ID, 1));
+		assert_ok!(StoneIndexPallet::enact_update(Origin::signed(TEST_ACCOUNT_ID), TEST_INDEX_ID));
+		assert_eq!(StoneIndexPallet::indexes(TEST_INDEX_ID).components, new_components);
+		assert_eq!(StoneIndexPallet::pending_updates(TEST_INDEX_ID), None);
+	});
+}
+
+#[test]
+fn update_index_without_supply_needs_no_deposit() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Assets::mint(10003, 2, 1));
+		assert_ok!(StoneIndexPallet::propose_update(
+			Origin::signed(TEST_ACCOUNT_ID),
+			TEST_INDEX_ID,
+			b"FirstIndex".to_vec(),
+			vec![component(10003, 7)]
+		));
+		run_to_block(11);
+		assert_eq!(StoneIndexPallet::indexes(TEST_INDEX_ID).components, vec![component(10003, 7)]);
+	});
+}
+
 #[test]
 fn buy_or_sell_non_existing_index() {
 	new_test_ext().execute_with(|| {
-		Assets::mint(10001, TEST_ACCOUNT_ID, 10000);
-		Assets::mint(10002, TEST_ACCOUNT_ID, 100);
+		assert_ok!(Assets::mint(10001, TEST_ACCOUNT_ID, 10000));
+		assert_ok!(Assets::mint(10002, TEST_ACCOUNT_ID, 100));
 		assert_noop!(
 			StoneIndexPallet::buy_index(Origin::signed(TEST_ACCOUNT_ID), 999999999, 1),
 			Error::<TestRuntime>::IndexNotExist
@@ -78,8 +586,8 @@ fn buy_or_sell_non_existing_index() {
 #[test]
 fn buy_too_much_index() {
 	new_test_ext().execute_with(|| {
-		Assets::mint(10001, TEST_ACCOUNT_ID, 10000);
-		Assets::mint(10002, TEST_ACCOUNT_ID, 100);
+		assert_ok!(Assets::mint(10001, TEST_ACCOUNT_ID, 10000));
+		assert_ok!(Assets::mint(10002, TEST_ACCOUNT_ID, 100));
 		assert_ok!(StoneIndexPallet::buy_index(
 			Origin::signed(TEST_ACCOUNT_ID),
 			TEST_INDEX_ID,
@@ -95,8 +603,8 @@ fn buy_too_much_index() {
 #[test]
 fn sell_too_much_index() {
 	new_test_ext().execute_with(|| {
-		Assets::mint(10001, TEST_ACCOUNT_ID, 10000);
-		Assets::mint(10002, TEST_ACCOUNT_ID, 100);
+		assert_ok!(Assets::mint(10001, TEST_ACCOUNT_ID, 10000));
+		assert_ok!(Assets::mint(10002, TEST_ACCOUNT_ID, 100));
 		assert_noop!(
 			StoneIndexPallet::sell_index(Origin::signed(TEST_ACCOUNT_ID), TEST_INDEX_ID, 100000000),
 			Error::<TestRuntime>::InsufficientIndexBalance
@@ -107,15 +615,15 @@ fn sell_too_much_index() {
 #[test]
 fn buy_and_sell_index() {
 	new_test_ext().execute_with(|| {
-		Assets::mint(10001, TEST_ACCOUNT_ID, 10000);
-		Assets::mint(10002, TEST_ACCOUNT_ID, 100);
+		assert_ok!(Assets::mint(10001, TEST_ACCOUNT_ID, 10000));
+		assert_ok!(Assets::mint(10002, TEST_ACCOUNT_ID, 100));
 		assert_ok!(StoneIndexPallet::buy_index(
 			Origin::signed(TEST_ACCOUNT_ID),
 			TEST_INDEX_ID,
 			5
 		));
 		assert_eq!(
-			StoneIndexPallet::index_balances((TEST_INDEX_ID, TEST_ACCOUNT_ID)),
+			StoneIndexPallet::index_balance(TEST_INDEX_ID, &TEST_ACCOUNT_ID),
 			5
 		);
 		assert_eq!(Assets::balance(10001, TEST_ACCOUNT_ID), 9990);
@@ -127,10 +635,1396 @@ fn buy_and_sell_index() {
 			1
 		));
 		assert_eq!(
-			StoneIndexPallet::index_balances((TEST_INDEX_ID, TEST_ACCOUNT_ID)),
+			StoneIndexPallet::index_balance(TEST_INDEX_ID, &TEST_ACCOUNT_ID),
 			4
 		);
 		assert_eq!(Assets::balance(10001, TEST_ACCOUNT_ID), 9992);
 		assert_eq!(Assets::balance(10002, TEST_ACCOUNT_ID), 96);
 	});
 }
+
+#[test]
+fn buy_and_sell_index_goes_through_vault() {
+	new_test_ext().execute_with(|| {
+		let vault = StoneIndexPallet::vault_account(TEST_INDEX_ID);
+		assert_ok!(Assets::mint(10001, TEST_ACCOUNT_ID, 10000));
+		assert_ok!(Assets::mint(10002, TEST_ACCOUNT_ID, 100));
+		assert_ok!(StoneIndexPallet::buy_index(
+			Origin::signed(TEST_ACCOUNT_ID),
+			TEST_INDEX_ID,
+			5
+		));
+		assert_eq!(Assets::balance(10001, vault), 10);
+		assert_eq!(Assets::balance(10002, vault), 5);
+		// Nothing is burnt: the assets are only held in custody.
+		assert_eq!(Assets::total_supply(10001), 10000);
+		assert_eq!(Assets::total_supply(10002), 100);
+
+		assert_ok!(StoneIndexPallet::sell_index(
+			Origin::signed(TEST_ACCOUNT_ID),
+			TEST_INDEX_ID,
+			5
+		));
+		assert_eq!(Assets::balance(10001, vault), 0);
+		assert_eq!(Assets::balance(10002, vault), 0);
+		assert_eq!(Assets::total_supply(10001), 10000);
+		assert_eq!(Assets::total_supply(10002), 100);
+	});
+}
+
+#[test]
+fn vaults_are_distinct_per_index() {
+	new_test_ext().execute_with(|| {
+		assert_ne!(StoneIndexPallet::vault_account(1), StoneIndexPallet::vault_account(2));
+		assert_ne!(StoneIndexPallet::vault_account(1), TEST_ACCOUNT_ID);
+	});
+}
+
+#[test]
+fn sell_index_fails_without_reserve() {
+	new_test_ext().execute_with(|| {
+		// Balances credited outside of `buy_index` are not backed by the vault.
+		assert_ok!(StoneIndexPallet::_mint(TEST_INDEX_ID, TEST_ACCOUNT_ID, 5));
+		assert_noop!(
+			StoneIndexPallet::sell_index(Origin::signed(TEST_ACCOUNT_ID), TEST_INDEX_ID, 5),
+			Error::<TestRuntime>::InsufficientReserve
+		);
+	});
+}
+
+#[test]
+fn total_supply_follows_buy_sell_and_transfer() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Assets::mint(10001, TEST_ACCOUNT_ID, 10000));
+		assert_ok!(Assets::mint(10002, TEST_ACCOUNT_ID, 100));
+		assert_eq!(StoneIndexPallet::index_total_supply(TEST_INDEX_ID), 0);
+
+		assert_ok!(StoneIndexPallet::buy_index(Origin::signed(TEST_ACCOUNT_ID), TEST_INDEX_ID, 10));
+		assert_eq!(StoneIndexPallet::index_total_supply(TEST_INDEX_ID), 10);
+		assert_eq!(sum_of_holder_balances(TEST_INDEX_ID), 10);
+
+		assert_ok!(StoneIndexPallet::transfer(Origin::signed(TEST_ACCOUNT_ID), TEST_INDEX_ID, 2, 4));
+		assert_eq!(StoneIndexPallet::index_total_supply(TEST_INDEX_ID), 10);
+		assert_eq!(sum_of_holder_balances(TEST_INDEX_ID), 10);
+
+		assert_ok!(StoneIndexPallet::sell_index(Origin::signed(2), TEST_INDEX_ID, 3));
+		assert_eq!(StoneIndexPallet::index_total_supply(TEST_INDEX_ID), 7);
+		assert_eq!(sum_of_holder_balances(TEST_INDEX_ID), 7);
+
+		assert_ok!(StoneIndexPallet::_mint(TEST_INDEX_ID, 3, 5));
+		assert_eq!(StoneIndexPallet::index_total_supply(TEST_INDEX_ID), 12);
+		assert_eq!(sum_of_holder_balances(TEST_INDEX_ID), 12);
+	});
+}
+
+#[test]
+fn genesis_balances_set_total_supply() {
+	new_test_ext_with_balances(vec![
+		(TEST_INDEX_ID, TEST_ACCOUNT_ID, 30),
+		(TEST_INDEX_ID, 2, 12),
+	]).execute_with(|| {
+		assert_eq!(StoneIndexPallet::index_balance(TEST_INDEX_ID, &TEST_ACCOUNT_ID), 30);
+		assert_eq!(StoneIndexPallet::index_total_supply(TEST_INDEX_ID), 42);
+		assert_eq!(sum_of_holder_balances(TEST_INDEX_ID), 42);
+	});
+}
+
+#[test]
+fn genesis_balances_are_backed_by_the_vault() {
+	new_test_ext_with_balances(vec![(TEST_INDEX_ID, TEST_ACCOUNT_ID, 6), (TEST_INDEX_ID, 2, 4)]).execute_with(|| {
+		let vault = StoneIndexPallet::vault_account(TEST_INDEX_ID);
+		assert_eq!(StoneIndexPallet::index_total_supply(TEST_INDEX_ID), 10);
+		assert_eq!(Assets::balance(10001, vault), 20);
+		assert_eq!(Assets::balance(10002, vault), 10);
+	});
+}
+
+#[test]
+fn buying_too_much_overflows() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Assets::mint(10001, TEST_ACCOUNT_ID, 10000));
+		assert_ok!(Assets::mint(10002, TEST_ACCOUNT_ID, 100));
+		// The first component has a weight of 2.
+		assert_noop!(
+			StoneIndexPallet::buy_index(Origin::signed(TEST_ACCOUNT_ID), TEST_INDEX_ID, u128::max_value() / 2 + 1),
+			Error::<TestRuntime>::Overflow
+		);
+	});
+}
+
+#[test]
+fn index_supply_cannot_overflow() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(StoneIndexPallet::_mint(TEST_INDEX_ID, TEST_ACCOUNT_ID, u128::max_value()));
+		assert_noop!(StoneIndexPallet::_mint(TEST_INDEX_ID, 2, 1), pallet_assets::Error::<TestRuntime>::Overflow);
+		assert_noop!(StoneIndexPallet::_burn(TEST_INDEX_ID, 2, 1), pallet_assets::Error::<TestRuntime>::Underflow);
+		assert_eq!(StoneIndexPallet::index_total_supply(TEST_INDEX_ID), u128::max_value());
+	});
+}
+
+#[test]
+fn set_fees() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			StoneIndexPallet::set_fees(Origin::signed(2), TEST_INDEX_ID, Permill::from_percent(1), Permill::zero()),
+			Error::<TestRuntime>::NotTheOwner
+		);
+		assert_noop!(
+			StoneIndexPallet::set_fees(
+				Origin::signed(TEST_ACCOUNT_ID),
+				TEST_INDEX_ID,
+				Permill::from_percent(6),
+				Permill::zero()
+			),
+			Error::<TestRuntime>::FeeTooHigh
+		);
+		assert_ok!(StoneIndexPallet::set_fees(
+			Origin::signed(TEST_ACCOUNT_ID),
+			TEST_INDEX_ID,
+			Permill::from_percent(1),
+			Permill::from_percent(2)
+		));
+		let index = StoneIndexPallet::indexes(TEST_INDEX_ID);
+		assert_eq!(index.mint_fee, Permill::from_percent(1));
+		assert_eq!(index.redeem_fee, Permill::from_percent(2));
+	});
+}
+
+#[test]
+fn buy_and_sell_pay_fees_to_owner() {
+	new_test_ext().execute_with(|| {
+		let holder = 2;
+		let vault = StoneIndexPallet::vault_account(TEST_INDEX_ID);
+		assert_ok!(Assets::mint(10001, holder, 200));
+		assert_ok!(Assets::mint(10002, holder, 100));
+		assert_ok!(StoneIndexPallet::set_fees(
+			Origin::signed(TEST_ACCOUNT_ID),
+			TEST_INDEX_ID,
+			Permill::from_percent(1),
+			Permill::from_percent(2)
+		));
+
+		// The whole basket is deposited but 1% of the units go to the owner.
+		assert_ok!(StoneIndexPallet::buy_index(Origin::signed(holder), TEST_INDEX_ID, 100));
+		assert_eq!(StoneIndexPallet::index_balance(TEST_INDEX_ID, &holder), 99);
+		assert_eq!(StoneIndexPallet::index_balance(TEST_INDEX_ID, &TEST_ACCOUNT_ID), 1);
+		assert_eq!(StoneIndexPallet::index_total_supply(TEST_INDEX_ID), 100);
+		assert_eq!(Assets::balance(10001, vault), 200);
+
+		// 2% of 50 units stay with the owner, the other 49 are redeemed.
+		assert_ok!(StoneIndexPallet::sell_index(Origin::signed(holder), TEST_INDEX_ID, 50));
+		assert_eq!(StoneIndexPallet::index_balance(TEST_INDEX_ID, &holder), 49);
+		assert_eq!(StoneIndexPallet::index_balance(TEST_INDEX_ID, &TEST_ACCOUNT_ID), 2);
+		assert_eq!(StoneIndexPallet::index_total_supply(TEST_INDEX_ID), 51);
+		assert_eq!(Assets::balance(10001, holder), 98);
+		assert_eq!(Assets::balance(10002, holder), 49);
+		assert_eq!(Assets::balance(10001, vault), 102);
+		assert_eq!(Assets::balance(10002, vault), 51);
+	});
+}
+
+#[test]
+fn fees_round_up() {
+	new_test_ext().execute_with(|| {
+		let holder = 2;
+		assert_ok!(Assets::mint(10001, holder, 20));
+		assert_ok!(Assets::mint(10002, holder, 10));
+		assert_ok!(StoneIndexPallet::set_fees(
+			Origin::signed(TEST_ACCOUNT_ID),
+			TEST_INDEX_ID,
+			Permill::from_percent(1),
+			Permill::zero()
+		));
+		assert_ok!(StoneIndexPallet::buy_index(Origin::signed(holder), TEST_INDEX_ID, 10));
+		assert_eq!(StoneIndexPallet::index_balance(TEST_INDEX_ID, &holder), 9);
+		assert_eq!(StoneIndexPallet::index_balance(TEST_INDEX_ID, &TEST_ACCOUNT_ID), 1);
+	});
+}
+
+// Give `holder` 1000 units of the genesis index with a 10% annual streaming fee.
+fn setup_streaming_fee(holder: u128) {
+	assert_ok!(Assets::mint(10001, holder, 2000));
+	assert_ok!(Assets::mint(10002, holder, 1000));
+	assert_ok!(StoneIndexPallet::buy_index(Origin::signed(holder), TEST_INDEX_ID, 1000));
+	assert_ok!(StoneIndexPallet::set_streaming_fee(
+		Origin::signed(TEST_ACCOUNT_ID),
+		TEST_INDEX_ID,
+		Perbill::from_percent(10)
+	));
+}
+
+#[test]
+fn set_streaming_fee() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			StoneIndexPallet::set_streaming_fee(Origin::signed(2), TEST_INDEX_ID, Perbill::from_percent(1)),
+			Error::<TestRuntime>::NotTheOwner
+		);
+		assert_noop!(
+			StoneIndexPallet::set_streaming_fee(
+				Origin::signed(TEST_ACCOUNT_ID),
+				TEST_INDEX_ID,
+				Perbill::from_percent(21)
+			),
+			Error::<TestRuntime>::FeeTooHigh
+		);
+		assert_ok!(StoneIndexPallet::set_streaming_fee(
+			Origin::signed(TEST_ACCOUNT_ID),
+			TEST_INDEX_ID,
+			Perbill::from_percent(2)
+		));
+		assert_eq!(StoneIndexPallet::indexes(TEST_INDEX_ID).streaming_fee, Perbill::from_percent(2));
+	});
+}
+
+#[test]
+fn streaming_fee_accrues_over_blocks() {
+	new_test_ext().execute_with(|| {
+		let holder = 2;
+		setup_streaming_fee(holder);
+
+		// A tenth of a year at 10% a year is 1% of the supply.
+		run_to_block(101);
+		assert_ok!(StoneIndexPallet::accrue_fees(Origin::signed(3), TEST_INDEX_ID));
+		assert_eq!(StoneIndexPallet::index_balance(TEST_INDEX_ID, &TEST_ACCOUNT_ID), 10);
+		assert_eq!(StoneIndexPallet::index_total_supply(TEST_INDEX_ID), 1010);
+		assert_eq!(StoneIndexPallet::last_fee_accrual(TEST_INDEX_ID), 101);
+
+		// Charging twice in the same block mints nothing more.
+		assert_ok!(StoneIndexPallet::accrue_fees(Origin::signed(3), TEST_INDEX_ID));
+		assert_eq!(StoneIndexPallet::index_total_supply(TEST_INDEX_ID), 1010);
+
+		// Half a year later, on a supply of 1010.
+		run_to_block(601);
+		assert_ok!(StoneIndexPallet::accrue_fees(Origin::signed(3), TEST_INDEX_ID));
+		assert_eq!(StoneIndexPallet::index_balance(TEST_INDEX_ID, &TEST_ACCOUNT_ID), 60);
+		assert_eq!(StoneIndexPallet::index_total_supply(TEST_INDEX_ID), 1060);
+		assert_eq!(StoneIndexPallet::index_balance(TEST_INDEX_ID, &holder), 1000);
+	});
+}
+
+#[test]
+fn streaming_fee_is_not_lost_to_frequent_accrual() {
+	new_test_ext().execute_with(|| {
+		setup_streaming_fee(2);
+
+		// Each block is worth a tenth of a unit, which rounds down to nothing, so the
+		// accrual keeps running until a whole unit is due.
+		for block in 2..=101 {
+			run_to_block(block);
+			assert_ok!(StoneIndexPallet::accrue_fees(Origin::signed(3), TEST_INDEX_ID));
+		}
+		assert_eq!(StoneIndexPallet::index_balance(TEST_INDEX_ID, &TEST_ACCOUNT_ID), 10);
+		assert_eq!(StoneIndexPallet::index_total_supply(TEST_INDEX_ID), 1010);
+	});
+}
+
+#[test]
+fn streaming_fee_accrues_on_trades_and_transfers() {
+	new_test_ext().execute_with(|| {
+		let holder = 2;
+		setup_streaming_fee(holder);
+
+		run_to_block(101);
+		assert_ok!(StoneIndexPallet::transfer(Origin::signed(holder), TEST_INDEX_ID, 3, 100));
+		assert_eq!(StoneIndexPallet::index_balance(TEST_INDEX_ID, &TEST_ACCOUNT_ID), 10);
+
+		run_to_block(201);
+		assert_ok!(StoneIndexPallet::sell_index(Origin::signed(3), TEST_INDEX_ID, 100));
+		assert_eq!(StoneIndexPallet::index_balance(TEST_INDEX_ID, &TEST_ACCOUNT_ID), 20);
+		assert_eq!(StoneIndexPallet::last_fee_accrual(TEST_INDEX_ID), 201);
+		assert_eq!(StoneIndexPallet::index_total_supply(TEST_INDEX_ID), 920);
+		assert_eq!(sum_of_holder_balances(TEST_INDEX_ID), 920);
+
+		// Each unit is now backed by 1000/1020 of its weights.
+		assert_eq!(Assets::balance(10001, 3), 196);
+		assert_eq!(Assets::balance(10002, 3), 98);
+	});
+}
+
+#[test]
+fn streaming_fee_units_stay_backed() {
+	new_test_ext().execute_with(|| {
+		let holder = 2;
+		setup_streaming_fee(holder);
+		run_to_block(101);
+		assert_ok!(StoneIndexPallet::accrue_fees(Origin::signed(3), TEST_INDEX_ID));
+
+		// Holders and the fee recipient can all redeem, with payouts rounded down.
+		assert_ok!(StoneIndexPallet::sell_index(Origin::signed(holder), TEST_INDEX_ID, 1000));
+		assert_eq!(Assets::balance(10001, holder), 1980);
+		assert_eq!(Assets::balance(10002, holder), 990);
+		assert_ok!(StoneIndexPallet::sell_index(Origin::signed(TEST_ACCOUNT_ID), TEST_INDEX_ID, 10));
+		assert_eq!(StoneIndexPallet::index_total_supply(TEST_INDEX_ID), 0);
+
+		let vault = StoneIndexPallet::vault_account(TEST_INDEX_ID);
+		assert_eq!(Assets::balance(10001, vault), 1);
+		assert_eq!(Assets::balance(10002, vault), 1);
+	});
+}
+
+#[test]
+fn streaming_fee_does_not_accrue_on_empty_index() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(StoneIndexPallet::set_streaming_fee(
+			Origin::signed(TEST_ACCOUNT_ID),
+			TEST_INDEX_ID,
+			Perbill::from_percent(10)
+		));
+		run_to_block(500);
+		assert_ok!(Assets::mint(10001, 2, 2000));
+		assert_ok!(Assets::mint(10002, 2, 1000));
+		assert_ok!(StoneIndexPallet::buy_index(Origin::signed(2), TEST_INDEX_ID, 1000));
+		assert_eq!(StoneIndexPallet::index_total_supply(TEST_INDEX_ID), 1000);
+		assert_eq!(StoneIndexPallet::index_balance(TEST_INDEX_ID, &TEST_ACCOUNT_ID), 0);
+	});
+}
+
+#[test]
+fn index_units_are_assets() {
+	new_test_ext().execute_with(|| {
+		issue_test_assets();
+		let asset_id = StoneIndexPallet::indexes(TEST_INDEX_ID).asset_id;
+		assert!(Assets::asset_exists(asset_id));
+
+		assert_ok!(StoneIndexPallet::buy_index(Origin::signed(TEST_ACCOUNT_ID), TEST_INDEX_ID, 10));
+		assert_eq!(Assets::balance(asset_id, TEST_ACCOUNT_ID), 10);
+		assert_eq!(Assets::total_supply(asset_id), 10);
+
+		// Units moved by the assets pallet can be redeemed like any other.
+		assert_ok!(Assets::transfer(Origin::signed(TEST_ACCOUNT_ID), asset_id, 2, 4));
+		assert_eq!(StoneIndexPallet::index_balance(TEST_INDEX_ID, &2), 4);
+		assert_ok!(StoneIndexPallet::sell_index(Origin::signed(2), TEST_INDEX_ID, 4));
+		assert_eq!(Assets::balance(10001, 2), 8);
+		assert_eq!(Assets::total_supply(asset_id), 6);
+	});
+}
+
+#[test]
+fn index_units_can_be_components() {
+	new_test_ext().execute_with(|| {
+		issue_test_assets();
+		assert_ok!(StoneIndexPallet::buy_index(Origin::signed(TEST_ACCOUNT_ID), TEST_INDEX_ID, 10));
+		let asset_id = StoneIndexPallet::indexes(TEST_INDEX_ID).asset_id;
+
+		assert_ok!(StoneIndexPallet::add_index(
+			Origin::signed(TEST_ACCOUNT_ID),
+			b"meta".to_vec(),
+			vec![component(asset_id, 2)],
+			CompositionMode::Quantity,
+			0
+		));
+		assert_ok!(StoneIndexPallet::buy_index(Origin::signed(TEST_ACCOUNT_ID), 2, 3));
+		assert_eq!(StoneIndexPallet::index_balance(TEST_INDEX_ID, &TEST_ACCOUNT_ID), 4);
+		assert_eq!(StoneIndexPallet::index_balance(TEST_INDEX_ID, &StoneIndexPallet::vault_account(2)), 6);
+	});
+}
+
+#[test]
+fn approve_and_transfer_from() {
+	new_test_ext_with_balances(vec![(TEST_INDEX_ID, TEST_ACCOUNT_ID, 100)]).execute_with(|| {
+		assert_noop!(
+			StoneIndexPallet::transfer_from(Origin::signed(2), TEST_INDEX_ID, TEST_ACCOUNT_ID, 3, 1),
+			Error::<TestRuntime>::InsufficientAllowance
+		);
+		assert_noop!(
+			StoneIndexPallet::approve(Origin::signed(TEST_ACCOUNT_ID), 9, 2, 30),
+			Error::<TestRuntime>::IndexNotExist
+		);
+		assert_ok!(StoneIndexPallet::approve(Origin::signed(TEST_ACCOUNT_ID), TEST_INDEX_ID, 2, 30));
+		assert_eq!(StoneIndexPallet::allowance(TEST_INDEX_ID, (TEST_ACCOUNT_ID, 2)), 30);
+
+		assert_ok!(StoneIndexPallet::transfer_from(Origin::signed(2), TEST_INDEX_ID, TEST_ACCOUNT_ID, 3, 20));
+		assert_eq!(StoneIndexPallet::index_balance(TEST_INDEX_ID, &3), 20);
+		assert_eq!(StoneIndexPallet::index_balance(TEST_INDEX_ID, &TEST_ACCOUNT_ID), 80);
+		assert_eq!(StoneIndexPallet::allowance(TEST_INDEX_ID, (TEST_ACCOUNT_ID, 2)), 10);
+
+		// The allowance is exhausted after spending the remaining 10 units.
+		assert_noop!(
+			StoneIndexPallet::transfer_from(Origin::signed(2), TEST_INDEX_ID, TEST_ACCOUNT_ID, 3, 11),
+			Error::<TestRuntime>::InsufficientAllowance
+		);
+		assert_ok!(StoneIndexPallet::transfer_from(Origin::signed(2), TEST_INDEX_ID, TEST_ACCOUNT_ID, 2, 10));
+		assert_eq!(StoneIndexPallet::allowance(TEST_INDEX_ID, (TEST_ACCOUNT_ID, 2)), 0);
+		assert_noop!(
+			StoneIndexPallet::transfer_from(Origin::signed(2), TEST_INDEX_ID, TEST_ACCOUNT_ID, 2, 1),
+			Error::<TestRuntime>::InsufficientAllowance
+		);
+	});
+}
+
+#[test]
+fn transfer_from_is_bounded_by_the_owner_balance() {
+	new_test_ext_with_balances(vec![(TEST_INDEX_ID, TEST_ACCOUNT_ID, 5)]).execute_with(|| {
+		assert_ok!(StoneIndexPallet::approve(Origin::signed(TEST_ACCOUNT_ID), TEST_INDEX_ID, 2, 30));
+		assert_noop!(
+			StoneIndexPallet::transfer_from(Origin::signed(2), TEST_INDEX_ID, TEST_ACCOUNT_ID, 3, 6),
+			Error::<TestRuntime>::InsufficientIndexBalance
+		);
+		assert_noop!(
+			StoneIndexPallet::transfer_from(Origin::signed(2), TEST_INDEX_ID, TEST_ACCOUNT_ID, 3, 0),
+			Error::<TestRuntime>::TransferAmountZero
+		);
+	});
+}
+
+#[test]
+fn increase_decrease_and_revoke_allowance() {
+	new_test_ext_with_balances(vec![(TEST_INDEX_ID, TEST_ACCOUNT_ID, 100)]).execute_with(|| {
+		assert_ok!(StoneIndexPallet::increase_allowance(Origin::signed(TEST_ACCOUNT_ID), TEST_INDEX_ID, 2, 10));
+		assert_ok!(StoneIndexPallet::increase_allowance(Origin::signed(TEST_ACCOUNT_ID), TEST_INDEX_ID, 2, 5));
+		assert_eq!(StoneIndexPallet::allowance(TEST_INDEX_ID, (TEST_ACCOUNT_ID, 2)), 15);
+		assert_noop!(
+			StoneIndexPallet::increase_allowance(Origin::signed(TEST_ACCOUNT_ID), TEST_INDEX_ID, 2, u128::max_value()),
+			Error::<TestRuntime>::Overflow
+		);
+
+		assert_ok!(StoneIndexPallet::decrease_allowance(Origin::signed(TEST_ACCOUNT_ID), TEST_INDEX_ID, 2, 4));
+		assert_eq!(StoneIndexPallet::allowance(TEST_INDEX_ID, (TEST_ACCOUNT_ID, 2)), 11);
+		assert_noop!(
+			StoneIndexPallet::decrease_allowance(Origin::signed(TEST_ACCOUNT_ID), TEST_INDEX_ID, 2, 12),
+			Error::<TestRuntime>::Underflow
+		);
+
+		// Approving zero revokes the allowance.
+		assert_ok!(StoneIndexPallet::approve(Origin::signed(TEST_ACCOUNT_ID), TEST_INDEX_ID, 2, 0));
+		assert_eq!(StoneIndexPallet::allowance(TEST_INDEX_ID, (TEST_ACCOUNT_ID, 2)), 0);
+		assert_noop!(
+			StoneIndexPallet::transfer_from(Origin::signed(2), TEST_INDEX_ID, TEST_ACCOUNT_ID, 3, 1),
+			Error::<TestRuntime>::InsufficientAllowance
+		);
+	});
+}
+
+#[test]
+fn buy_index_with_a_single_asset() {
+	new_test_ext().execute_with(|| {
+		let holder = 2;
+		assert_ok!(Assets::mint(10003, holder, 100));
+		assert_noop!(
+			StoneIndexPallet::buy_index_with(Origin::signed(holder), 9, 10, 10003, 100),
+			Error::<TestRuntime>::IndexNotExist
+		);
+
+		// 20 of 10001 and 10 of 10002 cost one unit more each.
+		assert_noop!(
+			StoneIndexPallet::buy_index_with(Origin::signed(holder), TEST_INDEX_ID, 10, 10003, 31),
+			DispatchError::Other("FlatFeeSwap: max_amount_in exceeded")
+		);
+		assert_ok!(StoneIndexPallet::buy_index_with(Origin::signed(holder), TEST_INDEX_ID, 10, 10003, 32));
+		assert_eq!(StoneIndexPallet::index_balance(TEST_INDEX_ID, &holder), 10);
+		assert_eq!(Assets::balance(10003, holder), 68);
+		assert_eq!(Assets::balance(10001, holder), 0);
+		assert_eq!(Assets::balance(10002, holder), 0);
+
+		let vault = StoneIndexPallet::vault_account(TEST_INDEX_ID);
+		assert_eq!(Assets::balance(10001, vault), 20);
+		assert_eq!(Assets::balance(10002, vault), 10);
+	});
+}
+
+#[test]
+fn buy_index_with_a_component_skips_its_swap() {
+	new_test_ext().execute_with(|| {
+		let holder = 2;
+		assert_ok!(Assets::mint(10001, holder, 100));
+
+		assert_noop!(
+			StoneIndexPallet::buy_index_with(Origin::signed(holder), TEST_INDEX_ID, 10, 10001, 19),
+			Error::<TestRuntime>::SlippageExceeded
+		);
+		assert_ok!(StoneIndexPallet::buy_index_with(Origin::signed(holder), TEST_INDEX_ID, 10, 10001, 31));
+		assert_eq!(StoneIndexPallet::index_balance(TEST_INDEX_ID, &holder), 10);
+		assert_eq!(Assets::balance(10001, holder), 69);
+	});
+}
+
+#[test]
+fn sell_index_to_a_single_asset() {
+	new_test_ext_with_balances(vec![(TEST_INDEX_ID, 2, 10)]).execute_with(|| {
+		let holder = 2;
+		let vault = StoneIndexPallet::vault_account(TEST_INDEX_ID);
+
+		// 8 of 10001 and 4 of 10002 are paid out and swapped for one unit less each.
+		assert_noop!(
+			StoneIndexPallet::sell_index_to(Origin::signed(holder), TEST_INDEX_ID, 4, 10003, 11),
+			Error::<TestRuntime>::SlippageExceeded
+		);
+		assert_ok!(StoneIndexPallet::sell_index_to(Origin::signed(holder), TEST_INDEX_ID, 4, 10003, 10));
+		assert_eq!(StoneIndexPallet::index_balance(TEST_INDEX_ID, &holder), 6);
+		assert_eq!(Assets::balance(10003, holder), 10);
+		assert_eq!(Assets::balance(10001, holder), 0);
+		assert_eq!(Assets::balance(10002, holder), 0);
+		assert_eq!(Assets::balance(10001, vault), 12);
+		assert_eq!(Assets::balance(10002, vault), 6);
+	});
+}
+
+#[test]
+fn sell_index_to_a_component_skips_its_swap() {
+	new_test_ext_with_balances(vec![(TEST_INDEX_ID, 2, 10)]).execute_with(|| {
+		let holder = 2;
+
+		assert_ok!(StoneIndexPallet::sell_index_to(Origin::signed(holder), TEST_INDEX_ID, 10, 10001, 29));
+		assert_eq!(Assets::balance(10001, holder), 29);
+		assert_eq!(Assets::balance(10002, holder), 0);
+		assert_eq!(StoneIndexPallet::index_total_supply(TEST_INDEX_ID), 0);
+	});
+}
+
+#[test]
+fn nav_and_aum_follow_component_prices() {
+	new_test_ext_with_balances(vec![(TEST_INDEX_ID, 2, 10)]).execute_with(|| {
+		set_price(10001, Some(Price::saturating_from_integer(3)));
+		set_price(10002, Some(Price::saturating_from_rational(3, 2)));
+
+		// One unit holds 2 of 10001 and 1 of 10002.
+		assert_eq!(StoneIndexPallet::nav(TEST_INDEX_ID), Ok(Price::saturating_from_rational(15, 2)));
+		assert_eq!(StoneIndexPallet::aum(TEST_INDEX_ID), Ok(Price::saturating_from_integer(75)));
+		assert_ok!(StoneIndexPallet::update_nav(Origin::signed(2), TEST_INDEX_ID));
+
+		set_price(10002, Some(Price::saturating_from_integer(4)));
+		assert_eq!(StoneIndexPallet::nav(TEST_INDEX_ID), Ok(Price::saturating_from_integer(10)));
+		assert_eq!(StoneIndexPallet::aum(TEST_INDEX_ID), Ok(Price::saturating_from_integer(100)));
+	});
+}
+
+#[test]
+fn nav_needs_a_price_for_every_component() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(StoneIndexPallet::update_nav(Origin::signed(2), 9), Error::<TestRuntime>::IndexNotExist);
+
+		set_price(10001, Some(Price::saturating_from_integer(3)));
+		assert_noop!(StoneIndexPallet::nav(TEST_INDEX_ID), Error::<TestRuntime>::PriceUnavailable);
+		assert_noop!(StoneIndexPallet::aum(TEST_INDEX_ID), Error::<TestRuntime>::PriceUnavailable);
+		assert_noop!(StoneIndexPallet::update_nav(Origin::signed(2), TEST_INDEX_ID), Error::<TestRuntime>::PriceUnavailable);
+
+		set_price(10002, Some(Price::saturating_from_integer(1)));
+		assert_eq!(StoneIndexPallet::nav(TEST_INDEX_ID), Ok(Price::saturating_from_integer(7)));
+		assert_eq!(StoneIndexPallet::aum(TEST_INDEX_ID), Ok(Price::saturating_from_integer(0)));
+	});
+}
+
+fn target(asset_id: u32, target: Permill) -> StoneIndexComponent<u32> {
+	StoneIndexComponent { asset_id, target, ..Default::default() }
+}
+
+#[test]
+fn value_weighted_index_derives_quantities_from_prices() {
+	new_test_ext().execute_with(|| {
+		issue_test_assets();
+		let targets = vec![target(10001, Permill::from_percent(50)), target(10002, Permill::from_percent(50))];
+		assert_noop!(
+			StoneIndexPallet::add_index(
+				Origin::signed(TEST_ACCOUNT_ID),
+				b"value".to_vec(),
+				targets.clone(),
+				CompositionMode::ValueWeighted,
+				0
+			),
+			Error::<TestRuntime>::PriceUnavailable
+		);
+
+		set_price(10001, Some(Price::saturating_from_integer(2)));
+		set_price(10002, Some(Price::saturating_from_integer(5)));
+		assert_ok!(StoneIndexPallet::add_index(
+			Origin::signed(TEST_ACCOUNT_ID),
+			b"value".to_vec(),
+			targets,
+			CompositionMode::ValueWeighted,
+			0
+		));
+
+		// A new unit is worth 100, half of it in each component.
+		let index = StoneIndexPallet::indexes(2);
+		assert_eq!(index.mode, CompositionMode::ValueWeighted);
+		assert_eq!(
+			index.components.iter().map(|comp| comp.weight).collect::<Vec<_>>(),
+			vec![FixedU128::saturating_from_integer(25), FixedU128::saturating_from_integer(10)]
+		);
+		assert_eq!(StoneIndexPallet::nav(2), Ok(Price::saturating_from_integer(100)));
+	});
+}
+
+#[test]
+fn value_weighted_targets_are_validated() {
+	new_test_ext().execute_with(|| {
+		issue_test_assets();
+		set_price(10001, Some(Price::saturating_from_integer(2)));
+		set_price(10002, Some(Price::saturating_from_integer(5)));
+		let add = |components| {
+			StoneIndexPallet::add_index(
+				Origin::signed(TEST_ACCOUNT_ID),
+				b"value".to_vec(),
+				components,
+				CompositionMode::ValueWeighted,
+				0
+			)
+		};
+
+		assert_noop!(
+			add(vec![target(10001, Permill::from_percent(50)), target(10002, Permill::from_percent(40))]),
+			Error::<TestRuntime>::InvalidAllocation
+		);
+		assert_noop!(
+			add(vec![target(10001, Permill::from_percent(100)), target(10002, Permill::from_percent(0))]),
+			Error::<TestRuntime>::InvalidAllocation
+		);
+		// At this price 1% of 100 is less than the smallest fixed-point amount of 10002.
+		set_price(10002, Some(Price::from_inner(u128::max_value())));
+		assert_noop!(
+			add(vec![target(10001, Permill::from_percent(99)), target(10002, Permill::from_percent(1))]),
+			Error::<TestRuntime>::ZeroWeight
+		);
+		assert_noop!(
+			StoneIndexPallet::propose_update(
+				Origin::signed(TEST_ACCOUNT_ID),
+				TEST_INDEX_ID,
+				b"value".to_vec(),
+				vec![target(10001, Permill::from_percent(100))]
+			),
+			Error::<TestRuntime>::ZeroWeight
+		);
+	});
+}
+
+#[test]
+fn value_weighted_update_rederives_quantities() {
+	new_test_ext().execute_with(|| {
+		issue_test_assets();
+		set_price(10001, Some(Price::saturating_from_integer(2)));
+		set_price(10002, Some(Price::saturating_from_integer(5)));
+		let targets = vec![target(10001, Permill::from_percent(50)), target(10002, Permill::from_percent(50))];
+		assert_ok!(StoneIndexPallet::add_index(
+			Origin::signed(TEST_ACCOUNT_ID),
+			b"value".to_vec(),
+			targets.clone(),
+			CompositionMode::ValueWeighted,
+			0
+		));
+		assert_ok!(StoneIndexPallet::buy_index(Origin::signed(TEST_ACCOUNT_ID), 2, 4));
+
+		assert_noop!(
+			StoneIndexPallet::propose_update(
+				Origin::signed(TEST_ACCOUNT_ID),
+				2,
+				b"value".to_vec(),
+				vec![target(10001, Permill::from_percent(60)), target(10002, Permill::from_percent(60))]
+			),
+			Error::<TestRuntime>::InvalidAllocation
+		);
+		assert_ok!(StoneIndexPallet::propose_update(Origin::signed(TEST_ACCOUNT_ID), 2, b"value".to_vec(), targets));
+
+		// 10001 doubled in price, so a unit worth 150 is split anew when the update is enacted.
+		set_price(10001, Some(Price::saturating_from_integer(4)));
+		run_to_block(11);
+		let index = StoneIndexPallet::indexes(2);
+		assert_eq!(
+			index.components.iter().map(|comp| comp.weight).collect::<Vec<_>>(),
+			vec![FixedU128::saturating_from_rational(75, 4), FixedU128::saturating_from_integer(15)]
+		);
+
+		let vault = StoneIndexPallet::vault_account(2);
+		assert_eq!(Assets::balance(10001, vault), 75);
+		assert_eq!(Assets::balance(10002, vault), 60);
+		assert_eq!(Assets::balance(10001, TEST_ACCOUNT_ID), 9925);
+		assert_eq!(Assets::balance(10002, TEST_ACCOUNT_ID), 40);
+	});
+}
+
+fn fractional(asset_id: u32, hundredths: u32) -> StoneIndexComponent<u32> {
+	StoneIndexComponent {
+		asset_id,
+		weight: FixedU128::saturating_from_rational(hundredths, 100),
+		..Default::default()
+	}
+}
+
+#[test]
+fn index_decimals_are_bounded() {
+	new_test_ext().execute_with(|| {
+		issue_test_assets();
+		assert_noop!(
+			StoneIndexPallet::add_index(
+				Origin::signed(TEST_ACCOUNT_ID),
+				b"precise".to_vec(),
+				vec![component(10001, 1)],
+				CompositionMode::Quantity,
+				19
+			),
+			Error::<TestRuntime>::TooManyDecimals
+		);
+		assert_ok!(StoneIndexPallet::add_index(
+			Origin::signed(TEST_ACCOUNT_ID),
+			b"precise".to_vec(),
+			vec![component(10001, 1)],
+			CompositionMode::Quantity,
+			18
+		));
+		assert_eq!(StoneIndexPallet::indexes(2).decimals, 18);
+	});
+}
+
+#[test]
+fn fractional_quantities_with_decimals() {
+	new_test_ext().execute_with(|| {
+		issue_test_assets();
+		// A whole unit, 100 of the smallest units, holds 37 of 10001 and 63 of 10002.
+		assert_ok!(StoneIndexPallet::add_index(
+			Origin::signed(TEST_ACCOUNT_ID),
+			b"basket".to_vec(),
+			vec![component(10001, 37), component(10002, 63)],
+			CompositionMode::Quantity,
+			2
+		));
+		let vault = StoneIndexPallet::vault_account(2);
+
+		assert_ok!(StoneIndexPallet::buy_index(Origin::signed(TEST_ACCOUNT_ID), 2, 50));
+		assert_eq!(Assets::balance(10001, vault), 19);
+		assert_eq!(Assets::balance(10002, vault), 32);
+
+		assert_ok!(StoneIndexPallet::sell_index(Origin::signed(TEST_ACCOUNT_ID), 2, 50));
+		assert_eq!(Assets::balance(10001, vault), 1);
+		assert_eq!(Assets::balance(10002, vault), 1);
+		assert_eq!(StoneIndexPallet::index_total_supply(2), 0);
+	});
+}
+
+#[test]
+fn dust_rounds_in_favour_of_the_vault() {
+	new_test_ext().execute_with(|| {
+		issue_test_assets();
+		// Less than one smallest unit of each component backs a smallest index unit.
+		assert_ok!(StoneIndexPallet::add_index(
+			Origin::signed(TEST_ACCOUNT_ID),
+			b"dust".to_vec(),
+			vec![fractional(10001, 37), fractional(10002, 63)],
+			CompositionMode::Quantity,
+			0
+		));
+		let vault = StoneIndexPallet::vault_account(2);
+
+		// Buying a single unit costs a whole unit of each component.
+		for _ in 0..3 {
+			assert_ok!(StoneIndexPallet::buy_index(Origin::signed(TEST_ACCOUNT_ID), 2, 1));
+		}
+		assert_eq!(Assets::balance(10001, vault), 3);
+		assert_eq!(Assets::balance(10002, vault), 3);
+
+		// Selling one unit pays nothing out, selling the other two pays 0.74 and 1.26 rounded down.
+		assert_ok!(StoneIndexPallet::sell_index(Origin::signed(TEST_ACCOUNT_ID), 2, 1));
+		assert_eq!(Assets::balance(10001, vault), 3);
+		assert_eq!(Assets::balance(10002, vault), 3);
+		assert_ok!(StoneIndexPallet::sell_index(Origin::signed(TEST_ACCOUNT_ID), 2, 2));
+		assert_eq!(Assets::balance(10001, vault), 3);
+		assert_eq!(Assets::balance(10002, vault), 2);
+		assert_eq!(StoneIndexPallet::index_total_supply(2), 0);
+	});
+}
+
+#[test]
+fn rounding_holds_past_u128() {
+	let (a, b, c) = (136_498_326_688_907_047_595_659_928_587_492_241_909, 800_875, 142_542_696_650_696_974_386_385);
+	// The remainder is more than half of `c`, so rounding to the nearest would round up.
+	assert_eq!(StoneIndexPallet::mul_div(a, b, c, Rounding::Down), Ok(766_914_755_758_157_686_024));
+	assert_eq!(StoneIndexPallet::mul_div(a, b, c, Rounding::Up), Ok(766_914_755_758_157_686_025));
+	assert_eq!(StoneIndexPallet::mul_div(u128::MAX, u128::MAX, u128::MAX, Rounding::Up), Ok(u128::MAX));
+	assert_eq!(StoneIndexPallet::mul_div(7, 3, 2, Rounding::Down), Ok(10));
+	assert_eq!(StoneIndexPallet::mul_div(7, 3, 2, Rounding::Up), Ok(11));
+}
+
+#[test]
+fn set_index_status() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			StoneIndexPallet::set_index_status(Origin::signed(2), TEST_INDEX_ID, IndexStatus::Frozen),
+			Error::<TestRuntime>::NotTheOperator
+		);
+		assert_noop!(
+			StoneIndexPallet::set_index_status(Origin::signed(TEST_ACCOUNT_ID), 9, IndexStatus::Frozen),
+			Error::<TestRuntime>::IndexNotExist
+		);
+		assert_ok!(StoneIndexPallet::set_index_status(
+			Origin::signed(TEST_ACCOUNT_ID),
+			TEST_INDEX_ID,
+			IndexStatus::MintPaused
+		));
+		assert_eq!(StoneIndexPallet::indexes(TEST_INDEX_ID).status, IndexStatus::MintPaused);
+
+		assert_noop!(
+			StoneIndexPallet::force_set_index_status(
+				Origin::signed(TEST_ACCOUNT_ID),
+				TEST_INDEX_ID,
+				IndexStatus::Active
+			),
+			DispatchError::BadOrigin
+		);
+		assert_ok!(StoneIndexPallet::force_set_index_status(Origin::root(), TEST_INDEX_ID, IndexStatus::Active));
+		assert_eq!(StoneIndexPallet::indexes(TEST_INDEX_ID).status, IndexStatus::Active);
+	});
+}
+
+#[test]
+fn paused_index_rejects_mints_or_redemptions() {
+	new_test_ext().execute_with(|| {
+		issue_test_assets();
+		assert_ok!(StoneIndexPallet::buy_index(Origin::signed(TEST_ACCOUNT_ID), TEST_INDEX_ID, 10));
+
+		assert_ok!(StoneIndexPallet::force_set_index_status(Origin::root(), TEST_INDEX_ID, IndexStatus::MintPaused));
+		assert_noop!(
+			StoneIndexPallet::buy_index(Origin::signed(TEST_ACCOUNT_ID), TEST_INDEX_ID, 1),
+			Error::<TestRuntime>::IndexPaused
+		);
+		assert_noop!(
+			StoneIndexPallet::buy_index_with(Origin::signed(TEST_ACCOUNT_ID), TEST_INDEX_ID, 1, 10001, 100),
+			Error::<TestRuntime>::IndexPaused
+		);
+		assert_ok!(StoneIndexPallet::sell_index(Origin::signed(TEST_ACCOUNT_ID), TEST_INDEX_ID, 1));
+
+		assert_ok!(StoneIndexPallet::force_set_index_status(Origin::root(), TEST_INDEX_ID, IndexStatus::RedeemPaused));
+		assert_noop!(
+			StoneIndexPallet::sell_index(Origin::signed(TEST_ACCOUNT_ID), TEST_INDEX_ID, 1),
+			Error::<TestRuntime>::IndexPaused
+		);
+		assert_noop!(
+			StoneIndexPallet::sell_index_to(Origin::signed(TEST_ACCOUNT_ID), TEST_INDEX_ID, 1, 10001, 0),
+			Error::<TestRuntime>::IndexPaused
+		);
+		assert_ok!(StoneIndexPallet::buy_index(Origin::signed(TEST_ACCOUNT_ID), TEST_INDEX_ID, 1));
+		assert_ok!(StoneIndexPallet::transfer(Origin::signed(TEST_ACCOUNT_ID), TEST_INDEX_ID, 2, 1));
+		assert_eq!(StoneIndexPallet::index_balance(TEST_INDEX_ID, &TEST_ACCOUNT_ID), 9);
+	});
+}
+
+#[test]
+fn frozen_index_rejects_everything() {
+	new_test_ext().execute_with(|| {
+		issue_test_assets();
+		assert_ok!(StoneIndexPallet::buy_index(Origin::signed(TEST_ACCOUNT_ID), TEST_INDEX_ID, 10));
+		assert_ok!(StoneIndexPallet::approve(Origin::signed(TEST_ACCOUNT_ID), TEST_INDEX_ID, 2, 5));
+
+		assert_ok!(StoneIndexPallet::set_index_status(Origin::signed(TEST_ACCOUNT_ID), TEST_INDEX_ID, IndexStatus::Frozen));
+		assert_noop!(
+			StoneIndexPallet::buy_index(Origin::signed(TEST_ACCOUNT_ID), TEST_INDEX_ID, 1),
+			Error::<TestRuntime>::IndexPaused
+		);
+		assert_noop!(
+			StoneIndexPallet::sell_index(Origin::signed(TEST_ACCOUNT_ID), TEST_INDEX_ID, 1),
+			Error::<TestRuntime>::IndexPaused
+		);
+		assert_noop!(
+			StoneIndexPallet::transfer(Origin::signed(TEST_ACCOUNT_ID), TEST_INDEX_ID, 2, 1),
+			Error::<TestRuntime>::IndexPaused
+		);
+		assert_noop!(
+			StoneIndexPallet::transfer_from(Origin::signed(2), TEST_INDEX_ID, TEST_ACCOUNT_ID, 3, 1),
+			Error::<TestRuntime>::IndexPaused
+		);
+		// Nor can the units be moved through the assets pallet.
+		let asset_id = StoneIndexPallet::indexes(TEST_INDEX_ID).asset_id;
+		assert_noop!(
+			Assets::transfer(Origin::signed(TEST_ACCOUNT_ID), asset_id, 2, 1),
+			Error::<TestRuntime>::IndexPaused
+		);
+
+		// Unfreezing restores every operation.
+		assert_ok!(StoneIndexPallet::set_index_status(Origin::signed(TEST_ACCOUNT_ID), TEST_INDEX_ID, IndexStatus::Active));
+		assert_ok!(StoneIndexPallet::transfer_from(Origin::signed(2), TEST_INDEX_ID, TEST_ACCOUNT_ID, 3, 1));
+		assert_ok!(StoneIndexPallet::sell_index(Origin::signed(TEST_ACCOUNT_ID), TEST_INDEX_ID, 1));
+		assert_ok!(StoneIndexPallet::buy_index(Origin::signed(TEST_ACCOUNT_ID), TEST_INDEX_ID, 1));
+		assert_eq!(StoneIndexPallet::index_balance(TEST_INDEX_ID, &TEST_ACCOUNT_ID), 9);
+	});
+}
+
+#[test]
+fn retire_index() {
+	new_test_ext_with_balances(vec![(TEST_INDEX_ID, 2, 10)]).execute_with(|| {
+		issue_test_assets();
+		assert_noop!(StoneIndexPallet::retire_index(Origin::signed(2), TEST_INDEX_ID), Error::<TestRuntime>::NotTheOwner);
+		assert_noop!(
+			StoneIndexPallet::force_retire_index(Origin::signed(TEST_ACCOUNT_ID), TEST_INDEX_ID),
+			DispatchError::BadOrigin
+		);
+		assert_ok!(StoneIndexPallet::propose_update(
+			Origin::signed(TEST_ACCOUNT_ID),
+			TEST_INDEX_ID,
+			b"next".to_vec(),
+			vec![component(10001, 1)]
+		));
+
+		// Only `retire_index` retires an index.
+		assert_noop!(
+			StoneIndexPallet::set_index_status(Origin::signed(TEST_ACCOUNT_ID), TEST_INDEX_ID, IndexStatus::Retired),
+			Error::<TestRuntime>::IndexRetired
+		);
+
+		assert_ok!(StoneIndexPallet::retire_index(Origin::signed(TEST_ACCOUNT_ID), TEST_INDEX_ID));
+		assert_eq!(StoneIndexPallet::indexes(TEST_INDEX_ID).status, IndexStatus::Retired);
+		assert_eq!(StoneIndexPallet::pending_updates(TEST_INDEX_ID), None);
+		assert_noop!(
+			StoneIndexPallet::force_retire_index(Origin::root(), TEST_INDEX_ID),
+			Error::<TestRuntime>::IndexRetired
+		);
+
+		// Retirement stops trading and cannot be undone.
+		assert_noop!(
+			StoneIndexPallet::buy_index(Origin::signed(TEST_ACCOUNT_ID), TEST_INDEX_ID, 1),
+			Error::<TestRuntime>::IndexPaused
+		);
+		assert_noop!(
+			StoneIndexPallet::sell_index(Origin::signed(2), TEST_INDEX_ID, 1),
+			Error::<TestRuntime>::IndexPaused
+		);
+		assert_noop!(
+			StoneIndexPallet::force_set_index_status(Origin::root(), TEST_INDEX_ID, IndexStatus::Active),
+			Error::<TestRuntime>::IndexRetired
+		);
+		assert_ok!(StoneIndexPallet::transfer(Origin::signed(2), TEST_INDEX_ID, 3, 4));
+	});
+}
+
+#[test]
+fn claim_settlement_pays_pro_rata() {
+	new_test_ext_with_balances(vec![(TEST_INDEX_ID, TEST_ACCOUNT_ID, 6), (TEST_INDEX_ID, 2, 4)]).execute_with(|| {
+		let vault = StoneIndexPallet::vault_account(TEST_INDEX_ID);
+		// One unit of 10001 more than the supply needs.
+		assert_ok!(Assets::mint(10001, vault, 1));
+		assert_ok!(StoneIndexPallet::approve(Origin::signed(TEST_ACCOUNT_ID), TEST_INDEX_ID, 3, 5));
+		assert_noop!(
+			StoneIndexPallet::claim_settlement(Origin::signed(2), TEST_INDEX_ID),
+			Error::<TestRuntime>::IndexNotRetired
+		);
+		assert_ok!(StoneIndexPallet::retire_index(Origin::signed(TEST_ACCOUNT_ID), TEST_INDEX_ID));
+
+		assert_noop!(
+			StoneIndexPallet::claim_settlement(Origin::signed(3), TEST_INDEX_ID),
+			Error::<TestRuntime>::InsufficientIndexBalance
+		);
+		// 4 of 10 units are worth 8.4 of 10001, rounded down, and 4 of 10002.
+		assert_ok!(StoneIndexPallet::claim_settlement(Origin::signed(2), TEST_INDEX_ID));
+		assert_eq!(Assets::balance(10001, 2), 8);
+		assert_eq!(Assets::balance(10002, 2), 4);
+		assert_eq!(StoneIndexPallet::index_total_supply(TEST_INDEX_ID), 6);
+		assert!(StoneIndexPallet::index_asset(TEST_INDEX_ID).is_some());
+
+		// The last holder takes whatever is left.
+		assert_ok!(StoneIndexPallet::claim_settlement(Origin::signed(TEST_ACCOUNT_ID), TEST_INDEX_ID));
+		assert_eq!(Assets::balance(10001, TEST_ACCOUNT_ID), 13);
+		assert_eq!(Assets::balance(10002, TEST_ACCOUNT_ID), 6);
+		assert_eq!(Assets::balance(10001, vault), 0);
+		assert_eq!(Assets::balance(10002, vault), 0);
+
+		assert!(StoneIndexPallet::index_asset(TEST_INDEX_ID).is_none());
+		assert_noop!(
+			StoneIndexPallet::claim_settlement(Origin::signed(TEST_ACCOUNT_ID), TEST_INDEX_ID),
+			Error::<TestRuntime>::IndexNotExist
+		);
+	});
+}
+
+#[test]
+fn removed_index_is_cleared_in_steps() {
+	new_test_ext().execute_with(|| {
+		issue_test_assets();
+		assert_ok!(StoneIndexPallet::buy_index(Origin::signed(TEST_ACCOUNT_ID), TEST_INDEX_ID, 5));
+		assert_ok!(StoneIndexPallet::approve(Origin::signed(TEST_ACCOUNT_ID), TEST_INDEX_ID, 2, 5));
+		assert_ok!(StoneIndexPallet::approve(Origin::signed(TEST_ACCOUNT_ID), TEST_INDEX_ID, 3, 5));
+		assert_noop!(
+			StoneIndexPallet::clear_removed_index(Origin::signed(2), TEST_INDEX_ID, 10),
+			Error::<TestRuntime>::NothingToClear
+		);
+		assert_ok!(StoneIndexPallet::retire_index(Origin::signed(TEST_ACCOUNT_ID), TEST_INDEX_ID));
+		assert_ok!(StoneIndexPallet::claim_settlement(Origin::signed(TEST_ACCOUNT_ID), TEST_INDEX_ID));
+		assert!(StoneIndexPallet::is_removed(TEST_INDEX_ID));
+
+		// The id cannot be reused before the two allowances and one mint record are cleared.
+		let components = vec![component(10001, 1)];
+		assert_noop!(
+			StoneIndexPallet::force_add_index(
+				Origin::root(),
+				TEST_INDEX_ID,
+				b"again".to_vec(),
+				components.clone(),
+				CompositionMode::Quantity,
+				0,
+				TEST_ACCOUNT_ID
+			),
+			Error::<TestRuntime>::IndexNotCleared
+		);
+		assert_ok!(StoneIndexPallet::clear_removed_index(Origin::signed(2), TEST_INDEX_ID, 2));
+		assert_eq!(StoneIndexPallet::allowance(TEST_INDEX_ID, (TEST_ACCOUNT_ID, 2)), 0);
+		assert_eq!(StoneIndexPallet::minted_by(TEST_INDEX_ID, TEST_ACCOUNT_ID), 5);
+		assert!(StoneIndexPallet::is_removed(TEST_INDEX_ID));
+		assert_ok!(StoneIndexPallet::clear_removed_index(Origin::signed(2), TEST_INDEX_ID, 2));
+		assert_eq!(StoneIndexPallet::minted_by(TEST_INDEX_ID, TEST_ACCOUNT_ID), 0);
+		assert!(!StoneIndexPallet::is_removed(TEST_INDEX_ID));
+
+		assert_ok!(StoneIndexPallet::force_add_index(
+			Origin::root(),
+			TEST_INDEX_ID,
+			b"again".to_vec(),
+			components,
+			CompositionMode::Quantity,
+			0,
+			TEST_ACCOUNT_ID
+		));
+	});
+}
+
+#[test]
+fn claim_settlement_rounds_down_with_large_balances() {
+	let (small, large) = (10_000_000_000_000_000_001, 20_000_000_000_000_000_000);
+	new_test_ext_with_balances(vec![(TEST_INDEX_ID, TEST_ACCOUNT_ID, small), (TEST_INDEX_ID, 2, large)]).execute_with(|| {
+		let vault = StoneIndexPallet::vault_account(TEST_INDEX_ID);
+		assert_ok!(Assets::mint(10001, vault, 1));
+		assert_ok!(StoneIndexPallet::retire_index(Origin::signed(TEST_ACCOUNT_ID), TEST_INDEX_ID));
+
+		// The extra unit of 10001 is two thirds of a unit for account 2, more than half but still dust.
+		assert_ok!(StoneIndexPallet::claim_settlement(Origin::signed(2), TEST_INDEX_ID));
+		assert_eq!(Assets::balance(10001, 2), 2 * large);
+		assert_eq!(Assets::balance(10002, 2), large);
+		assert_ok!(StoneIndexPallet::claim_settlement(Origin::signed(TEST_ACCOUNT_ID), TEST_INDEX_ID));
+		assert_eq!(Assets::balance(10001, TEST_ACCOUNT_ID), 2 * small + 1);
+		assert_eq!(Assets::balance(10002, TEST_ACCOUNT_ID), small);
+	});
+}
+
+#[test]
+fn retiring_an_index_without_supply_removes_it() {
+	new_test_ext().execute_with(|| {
+		let vault = StoneIndexPallet::vault_account(TEST_INDEX_ID);
+		assert_ok!(Assets::mint(10001, vault, 3));
+
+		assert_ok!(StoneIndexPallet::force_retire_index(Origin::root(), TEST_INDEX_ID));
+		assert!(StoneIndexPallet::index_asset(TEST_INDEX_ID).is_none());
+		// Leftovers in the vault go to the owner.
+		assert_eq!(Assets::balance(10001, vault), 0);
+		assert_eq!(Assets::balance(10001, TEST_ACCOUNT_ID), 3);
+	});
+}
+
+#[test]
+fn transfer_ownership_needs_acceptance() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			StoneIndexPallet::transfer_ownership(Origin::signed(2), TEST_INDEX_ID, 2),
+			Error::<TestRuntime>::NotTheOwner
+		);
+		assert_ok!(StoneIndexPallet::transfer_ownership(Origin::signed(TEST_ACCOUNT_ID), TEST_INDEX_ID, 2));
+		assert_eq!(StoneIndexPallet::pending_owner(TEST_INDEX_ID), Some(2));
+		assert_eq!(StoneIndexPallet::indexes(TEST_INDEX_ID).owner, TEST_ACCOUNT_ID);
+		assert_noop!(
+			StoneIndexPallet::accept_ownership(Origin::signed(3), TEST_INDEX_ID),
+			Error::<TestRuntime>::NotThePendingOwner
+		);
+
+		// A new offer replaces the previous one.
+		assert_ok!(StoneIndexPallet::transfer_ownership(Origin::signed(TEST_ACCOUNT_ID), TEST_INDEX_ID, 3));
+		assert_noop!(
+			StoneIndexPallet::accept_ownership(Origin::signed(2), TEST_INDEX_ID),
+			Error::<TestRuntime>::NotThePendingOwner
+		);
+		assert_ok!(StoneIndexPallet::accept_ownership(Origin::signed(3), TEST_INDEX_ID));
+		assert_eq!(StoneIndexPallet::indexes(TEST_INDEX_ID).owner, 3);
+		assert_eq!(StoneIndexPallet::pending_owner(TEST_INDEX_ID), None);
+		assert_noop!(
+			StoneIndexPallet::set_fees(Origin::signed(TEST_ACCOUNT_ID), TEST_INDEX_ID, Permill::zero(), Permill::zero()),
+			Error::<TestRuntime>::NotTheOwner
+		);
+
+		// A forced transfer withdraws any offer.
+		assert_ok!(StoneIndexPallet::transfer_ownership(Origin::signed(3), TEST_INDEX_ID, 2));
+		assert_ok!(StoneIndexPallet::force_transfer_ownership(Origin::root(), TEST_INDEX_ID, 4));
+		assert_eq!(StoneIndexPallet::pending_owner(TEST_INDEX_ID), None);
+		assert_noop!(
+			StoneIndexPallet::accept_ownership(Origin::signed(2), TEST_INDEX_ID),
+			Error::<TestRuntime>::NotThePendingOwner
+		);
+	});
+}
+
+#[test]
+fn ownership_transfer_drops_the_operator() {
+	new_test_ext().execute_with(|| {
+		issue_test_assets();
+		let operator = 2;
+		assert_ok!(StoneIndexPallet::set_operator(Origin::signed(TEST_ACCOUNT_ID), TEST_INDEX_ID, Some(operator)));
+		assert_ok!(StoneIndexPallet::transfer_ownership(Origin::signed(TEST_ACCOUNT_ID), TEST_INDEX_ID, 3));
+		assert_ok!(StoneIndexPallet::accept_ownership(Origin::signed(3), TEST_INDEX_ID));
+		assert_eq!(StoneIndexPallet::indexes(TEST_INDEX_ID).operator, None);
+		assert_noop!(
+			StoneIndexPallet::set_index_status(Origin::signed(operator), TEST_INDEX_ID, IndexStatus::MintPaused),
+			Error::<TestRuntime>::NotTheOperator
+		);
+		assert_noop!(
+			StoneIndexPallet::propose_update(Origin::signed(operator), TEST_INDEX_ID, b"stale".to_vec(), vec![component(10001, 1)]),
+			Error::<TestRuntime>::NotTheOperator
+		);
+
+		assert_ok!(StoneIndexPallet::set_operator(Origin::signed(3), TEST_INDEX_ID, Some(operator)));
+		assert_ok!(StoneIndexPallet::force_transfer_ownership(Origin::root(), TEST_INDEX_ID, 4));
+		assert_eq!(StoneIndexPallet::indexes(TEST_INDEX_ID).operator, None);
+	});
+}
+
+#[test]
+fn ownership_transfer_cancels_the_pending_update() {
+	new_test_ext().execute_with(|| {
+		issue_test_assets();
+		assert_ok!(StoneIndexPallet::propose_update(
+			Origin::signed(TEST_ACCOUNT_ID),
+			TEST_INDEX_ID,
+			b"stale".to_vec(),
+			vec![component(10001, 1)]
+		));
+		let enact_at = StoneIndexPallet::pending_updates(TEST_INDEX_ID).unwrap().enact_at;
+		assert_ok!(StoneIndexPallet::transfer_ownership(Origin::signed(TEST_ACCOUNT_ID), TEST_INDEX_ID, 2));
+		assert_ok!(StoneIndexPallet::accept_ownership(Origin::signed(2), TEST_INDEX_ID));
+		assert!(StoneIndexPallet::pending_updates(TEST_INDEX_ID).is_none());
+		assert!(StoneIndexPallet::update_schedule(enact_at).is_empty());
+		run_to_block(enact_at);
+		assert_eq!(StoneIndexPallet::indexes(TEST_INDEX_ID).name, b"FirstIndex".to_vec());
+
+		assert_ok!(StoneIndexPallet::propose_update(Origin::signed(2), TEST_INDEX_ID, b"stale".to_vec(), vec![component(10001, 1)]));
+		assert_ok!(StoneIndexPallet::force_transfer_ownership(Origin::root(), TEST_INDEX_ID, 3));
+		assert!(StoneIndexPallet::pending_updates(TEST_INDEX_ID).is_none());
+	});
+}
+
+#[test]
+fn operator_can_update_and_pause_but_not_change_fees_or_ownership() {
+	new_test_ext().execute_with(|| {
+		issue_test_assets();
+		let operator = 2;
+		assert_noop!(
+			StoneIndexPallet::set_operator(Origin::signed(operator), TEST_INDEX_ID, Some(operator)),
+			Error::<TestRuntime>::NotTheOwner
+		);
+		assert_ok!(StoneIndexPallet::set_operator(Origin::signed(TEST_ACCOUNT_ID), TEST_INDEX_ID, Some(operator)));
+		assert_eq!(StoneIndexPallet::indexes(TEST_INDEX_ID).operator, Some(operator));
+
+		assert_ok!(StoneIndexPallet::set_index_status(Origin::signed(operator), TEST_INDEX_ID, IndexStatus::MintPaused));
+		assert_ok!(StoneIndexPallet::propose_update(
+			Origin::signed(operator),
+			TEST_INDEX_ID,
+			b"operated".to_vec(),
+			vec![component(10001, 1)]
+		));
+		assert_ok!(StoneIndexPallet::cancel_update(Origin::signed(operator), TEST_INDEX_ID));
+		assert_noop!(
+			StoneIndexPallet::propose_update(Origin::signed(3), TEST_INDEX_ID, b"other".to_vec(), vec![component(10001, 1)]),
+			Error::<TestRuntime>::NotTheOperator
+		);
+
+		assert_noop!(
+			StoneIndexPallet::set_fees(Origin::signed(operator), TEST_INDEX_ID, Permill::zero(), Permill::zero()),
+			Error::<TestRuntime>::NotTheOwner
+		);
+		assert_noop!(
+			StoneIndexPallet::set_streaming_fee(Origin::signed(operator), TEST_INDEX_ID, Perbill::zero()),
+			Error::<TestRuntime>::NotTheOwner
+		);
+		assert_noop!(
+			StoneIndexPallet::transfer_ownership(Origin::signed(operator), TEST_INDEX_ID, operator),
+			Error::<TestRuntime>::NotTheOwner
+		);
+		assert_noop!(
+			StoneIndexPallet::set_operator(Origin::signed(operator), TEST_INDEX_ID, None),
+			Error::<TestRuntime>::NotTheOwner
+		);
+		assert_noop!(
+			StoneIndexPallet::retire_index(Origin::signed(operator), TEST_INDEX_ID),
+			Error::<TestRuntime>::NotTheOwner
+		);
+
+		assert_ok!(StoneIndexPallet::set_operator(Origin::signed(TEST_ACCOUNT_ID), TEST_INDEX_ID, None));
+		assert_noop!(
+			StoneIndexPallet::set_index_status(Origin::signed(operator), TEST_INDEX_ID, IndexStatus::Active),
+			Error::<TestRuntime>::NotTheOperator
+		);
+	});
+}
+
+#[test]
+fn set_and_clear_metadata() {
+	new_test_ext().execute_with(|| {
+		let url = Some(b"https://stone.fi".to_vec());
+		assert_noop!(
+			StoneIndexPallet::set_metadata(Origin::signed(2), TEST_INDEX_ID, b"iDOT".to_vec(), vec![], None),
+			Error::<TestRuntime>::NotTheOwner
+		);
+		assert_noop!(
+			StoneIndexPallet::set_metadata(
+				Origin::signed(TEST_ACCOUNT_ID),
+				TEST_INDEX_ID,
+				vec![b'x'; 9],
+				vec![],
+				None
+			),
+			Error::<TestRuntime>::BadMetadata
+		);
+		assert_noop!(
+			StoneIndexPallet::set_metadata(
+				Origin::signed(TEST_ACCOUNT_ID),
+				TEST_INDEX_ID,
+				b"iDOT".to_vec(),
+				vec![],
+				Some(vec![b'x'; 33])
+			),
+			Error::<TestRuntime>::BadMetadata
+		);
+
+		// 10 plus one per byte of the 4 byte symbol, 17 byte description and 16 byte URL.
+		assert_ok!(StoneIndexPallet::set_metadata(
+			Origin::signed(TEST_ACCOUNT_ID),
+			TEST_INDEX_ID,
+			b"iDOT".to_vec(),
+			b"Liquid DOT basket".to_vec(),
+			url.clone()
+		));
+		assert_eq!(Balances::reserved_balance(TEST_ACCOUNT_ID), 47);
+		let metadata = StoneIndexPallet::get_metadata(&TEST_INDEX_ID).unwrap();
+		assert_eq!(metadata.symbol, b"iDOT".to_vec());
+		assert_eq!(metadata.decimals, StoneIndexPallet::indexes(TEST_INDEX_ID).decimals);
+		assert_eq!(metadata.url, url);
+
+		// New metadata replaces the deposit of the old one.
+		assert_ok!(StoneIndexPallet::set_metadata(
+			Origin::signed(TEST_ACCOUNT_ID),
+			TEST_INDEX_ID,
+			b"iDOT".to_vec(),
+			vec![],
+			None
+		));
+		assert_eq!(Balances::reserved_balance(TEST_ACCOUNT_ID), 14);
+
+		assert_noop!(
+			StoneIndexPallet::clear_metadata(Origin::signed(2), TEST_INDEX_ID),
+			Error::<TestRuntime>::NotTheOwner
+		);
+		assert_ok!(StoneIndexPallet::clear_metadata(Origin::signed(TEST_ACCOUNT_ID), TEST_INDEX_ID));
+		assert_eq!(Balances::reserved_balance(TEST_ACCOUNT_ID), 0);
+		assert_eq!(StoneIndexPallet::get_metadata(&TEST_INDEX_ID), None);
+		assert_noop!(
+			StoneIndexPallet::clear_metadata(Origin::signed(TEST_ACCOUNT_ID), TEST_INDEX_ID),
+			Error::<TestRuntime>::NoMetadata
+		);
+	});
+}
+
+#[test]
+fn metadata_deposit_moves_to_the_new_owner() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(StoneIndexPallet::set_metadata(
+			Origin::signed(TEST_ACCOUNT_ID),
+			TEST_INDEX_ID,
+			b"iDOT".to_vec(),
+			vec![],
+			None
+		));
+		assert_ok!(StoneIndexPallet::force_transfer_ownership(Origin::root(), TEST_INDEX_ID, 2));
+		assert_eq!(Balances::reserved_balance(TEST_ACCOUNT_ID), 0);
+		assert_eq!(Balances::reserved_balance(2), 14);
+		assert_eq!(StoneIndexPallet::get_metadata(&TEST_INDEX_ID).unwrap().depositor, 2);
+
+		// Account 3 has nothing to reserve, so the metadata is dropped.
+		assert_ok!(StoneIndexPallet::transfer_ownership(Origin::signed(2), TEST_INDEX_ID, 3));
+		assert_ok!(StoneIndexPallet::accept_ownership(Origin::signed(3), TEST_INDEX_ID));
+		assert_eq!(Balances::reserved_balance(2), 0);
+		assert_eq!(StoneIndexPallet::get_metadata(&TEST_INDEX_ID), None);
+		assert_noop!(
+			StoneIndexPallet::set_metadata(Origin::signed(3), TEST_INDEX_ID, b"iDOT".to_vec(), vec![], None),
+			pallet_balances::Error::<TestRuntime, _>::InsufficientBalance
+		);
+
+		// Removing a retired index releases the deposit.
+		assert_ok!(StoneIndexPallet::force_transfer_ownership(Origin::root(), TEST_INDEX_ID, 2));
+		assert_ok!(StoneIndexPallet::set_metadata(
+			Origin::signed(2),
+			TEST_INDEX_ID,
+			b"iDOT".to_vec(),
+			vec![],
+			None
+		));
+		assert_ok!(StoneIndexPallet::retire_index(Origin::signed(2), TEST_INDEX_ID));
+		assert_eq!(StoneIndexPallet::get_metadata(&TEST_INDEX_ID), None);
+		assert_eq!(Balances::reserved_balance(2), 0);
+	});
+}
+
+#[test]
+fn buying_is_bounded_by_the_supply_cap() {
+	new_test_ext().execute_with(|| {
+		issue_test_assets();
+		assert_ok!(Assets::mint(10001, 2, 100));
+		assert_ok!(Assets::mint(10002, 2, 50));
+		assert_noop!(
+			StoneIndexPallet::set_max_supply(Origin::signed(2), TEST_INDEX_ID, Some(10)),
+			Error::<TestRuntime>::NotTheOwner
+		);
+		assert_ok!(StoneIndexPallet::set_max_supply(Origin::signed(TEST_ACCOUNT_ID), TEST_INDEX_ID, Some(10)));
+		assert_eq!(StoneIndexPallet::mint_limits(TEST_INDEX_ID).max_supply, Some(10));
+
+		assert_ok!(StoneIndexPallet::buy_index(Origin::signed(TEST_ACCOUNT_ID), TEST_INDEX_ID, 6));
+		assert_noop!(
+			StoneIndexPallet::buy_index(Origin::signed(2), TEST_INDEX_ID, 5),
+			Error::<TestRuntime>::SupplyCapExceeded
+		);
+		assert_ok!(StoneIndexPallet::buy_index(Origin::signed(2), TEST_INDEX_ID, 4));
+		assert_eq!(StoneIndexPallet::index_total_supply(TEST_INDEX_ID), 10);
+
+		// Redeeming makes room under the cap again.
+		assert_ok!(StoneIndexPallet::sell_index(Origin::signed(2), TEST_INDEX_ID, 2));
+		assert_ok!(StoneIndexPallet::buy_index(Origin::signed(2), TEST_INDEX_ID, 2));
+
+		// A cap below the supply only stops buying.
+		assert_ok!(StoneIndexPallet::set_max_supply(Origin::signed(TEST_ACCOUNT_ID), TEST_INDEX_ID, Some(5)));
+		assert_noop!(
+			StoneIndexPallet::buy_index(Origin::signed(2), TEST_INDEX_ID, 1),
+			Error::<TestRuntime>::SupplyCapExceeded
+		);
+		assert_ok!(StoneIndexPallet::sell_index(Origin::signed(2), TEST_INDEX_ID, 1));
+
+		assert_ok!(StoneIndexPallet::set_max_supply(Origin::signed(TEST_ACCOUNT_ID), TEST_INDEX_ID, None));
+		assert_ok!(StoneIndexPallet::buy_index(Origin::signed(2), TEST_INDEX_ID, 11));
+	});
+}
+
+#[test]
+fn buying_is_bounded_by_the_mint_limits() {
+	new_test_ext().execute_with(|| {
+		issue_test_assets();
+		assert_ok!(Assets::mint(10001, 2, 100));
+		assert_ok!(Assets::mint(10002, 2, 50));
+		System::set_block_number(1);
+		assert_noop!(
+			StoneIndexPallet::set_mint_limits(Origin::signed(2), TEST_INDEX_ID, Some(5), Some(8)),
+			Error::<TestRuntime>::NotTheOwner
+		);
+		assert_ok!(StoneIndexPallet::set_mint_limits(Origin::signed(TEST_ACCOUNT_ID), TEST_INDEX_ID, Some(5), Some(8)));
+
+		assert_ok!(StoneIndexPallet::buy_index(Origin::signed(TEST_ACCOUNT_ID), TEST_INDEX_ID, 5));
+		assert_noop!(
+			StoneIndexPallet::buy_index(Origin::signed(TEST_ACCOUNT_ID), TEST_INDEX_ID, 1),
+			Error::<TestRuntime>::MintLimitExceeded
+		);
+		// Only 3 of the 8 units allowed in this block are left.
+		assert_noop!(
+			StoneIndexPallet::buy_index(Origin::signed(2), TEST_INDEX_ID, 4),
+			Error::<TestRuntime>::MintLimitExceeded
+		);
+		assert_ok!(StoneIndexPallet::buy_index(Origin::signed(2), TEST_INDEX_ID, 3));
+
+		// The block limit resets with the next block, the account limit does not.
+		System::set_block_number(2);
+		assert_ok!(StoneIndexPallet::buy_index(Origin::signed(2), TEST_INDEX_ID, 2));
+		assert_eq!(StoneIndexPallet::minted_by(TEST_INDEX_ID, 2), 5);
+		assert_noop!(
+			StoneIndexPallet::buy_index(Origin::signed(2), TEST_INDEX_ID, 1),
+			Error::<TestRuntime>::MintLimitExceeded
+		);
+
+		assert_ok!(StoneIndexPallet::set_mint_limits(Origin::signed(TEST_ACCOUNT_ID), TEST_INDEX_ID, None, None));
+		assert_ok!(StoneIndexPallet::buy_index(Origin::signed(2), TEST_INDEX_ID, 10));
+	});
+}
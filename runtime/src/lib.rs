@@ -9,12 +9,13 @@ include!(concat!(env!("OUT_DIR"), "/wasm_binary.rs"));
 use sp_std::prelude::*;
 use sp_core::{crypto::KeyTypeId, OpaqueMetadata};
 use sp_runtime::{
-	ApplyExtrinsicResult, generic, create_runtime_str, impl_opaque_keys, MultiSignature,
+	ApplyExtrinsicResult, DispatchError, FixedPointNumber, generic, create_runtime_str, impl_opaque_keys, MultiSignature, ModuleId,
 	transaction_validity::{TransactionValidity, TransactionSource},
 };
 use sp_runtime::traits::{
-	BlakeTwo256, Block as BlockT, AccountIdLookup, Verify, IdentifyAccount, NumberFor,
+	BlakeTwo256, Block as BlockT, AccountIdLookup, Verify, IdentifyAccount, NumberFor, SaturatedConversion,
 };
+use codec::Encode;
 use sp_api::impl_runtime_apis;
 use sp_consensus_aura::sr25519::AuthorityId as AuraId;
 use pallet_grandpa::{AuthorityId as GrandpaId, AuthorityList as GrandpaAuthorityList};
@@ -31,7 +32,7 @@ pub use pallet_balances::Call as BalancesCall;
 pub use sp_runtime::{Permill, Perbill};
 pub use frame_support::{
 	construct_runtime, parameter_types, StorageValue,
-	traits::{KeyOwnerProofSystem, Randomness},
+	traits::{Contains, KeyOwnerProofSystem, Randomness},
 	weights::{
 		Weight, IdentityFee,
 		constants::{BlockExecutionWeight, ExtrinsicBaseWeight, RocksDbWeight, WEIGHT_PER_SECOND},
@@ -88,6 +89,7 @@ pub mod opaque {
 		pub struct SessionKeys {
 			pub aura: Aura,
 			pub grandpa: Grandpa,
+			pub oracle: Oracle,
 		}
 	}
 }
@@ -269,12 +271,158 @@ impl pallet_assets::Config for Runtime {
 	type Event = Event;
 	type Balance = Balance;
 	type AssetId = u32;
+	type TransferGuard = StoneIndex;
+}
+
+parameter_types! {
+	pub const StoneIndexModuleId: ModuleId = ModuleId(*b"stone/ix");
+	pub const MaxComponents: u32 = 16;
+	pub const MaxNameLength: u32 = 64;
+	pub const UpdateDelay: BlockNumber = 2 * DAYS;
+	pub const MaxIndexFee: Permill = Permill::from_percent(5);
+	pub const IndexFeeRecipient: Option<AccountId> = None;
+	pub const BlocksPerYear: BlockNumber = 365 * DAYS;
+	pub const MaxStreamingFee: Perbill = Perbill::from_percent(5);
+	pub InitialUnitValue: pallet_oracle::Price = pallet_oracle::Price::saturating_from_integer(100);
+	pub const MetadataDepositBase: Balance = 50_000;
+	pub const MetadataDepositPerByte: Balance = 500;
+	pub const MaxSymbolLength: u32 = 16;
+	pub const MaxDescriptionLength: u32 = 256;
+}
+
+/// The Stone team manages every basket, so only the sudo key may create indexes.
+pub struct IndexCreators;
+impl Contains<AccountId> for IndexCreators {
+	fn sorted_members() -> Vec<AccountId> {
+		vec![Sudo::key()]
+	}
 }
 
 /// Configure the template pallet in pallets/template.
 impl pallet_stone_index::Config for Runtime {
 	type Event = Event;
 	type IndexId = u32;
+	type ModuleId = StoneIndexModuleId;
+	type MaxComponents = MaxComponents;
+	type MaxNameLength = MaxNameLength;
+	type UpdateDelay = UpdateDelay;
+	type CreateOrigin = frame_system::EnsureSignedBy<IndexCreators, AccountId>;
+	type AdminOrigin = frame_system::EnsureRoot<AccountId>;
+	type FeeRecipient = IndexFeeRecipient;
+	type MaxFee = MaxIndexFee;
+	type BlocksPerYear = BlocksPerYear;
+	type MaxStreamingFee = MaxStreamingFee;
+	type Swap = DexSwap;
+	type PriceProvider = Oracle;
+	type InitialUnitValue = InitialUnitValue;
+	type Currency = Balances;
+	type MetadataDepositBase = MetadataDepositBase;
+	type MetadataDepositPerByte = MetadataDepositPerByte;
+	type MaxSymbolLength = MaxSymbolLength;
+	type MaxDescriptionLength = MaxDescriptionLength;
+}
+
+parameter_types! {
+	pub const DexModuleId: ModuleId = ModuleId(*b"stone/dx");
+	pub const ExchangeFee: Permill = Permill::from_parts(3_000);
+	pub const MinimumLiquidity: Balance = 1_000;
+}
+
+impl pallet_dex::Config for Runtime {
+	type Event = Event;
+	type ModuleId = DexModuleId;
+	type ExchangeFee = ExchangeFee;
+	type MinimumLiquidity = MinimumLiquidity;
+}
+
+parameter_types! {
+	pub const MaxFeeders: u32 = 16;
+	pub const MaxPriceAge: BlockNumber = 10 * MINUTES;
+	pub const FeedInterval: BlockNumber = MINUTES;
+}
+
+impl pallet_oracle::Config for Runtime {
+	type Event = Event;
+	type AuthorityId = pallet_oracle::crypto::OracleAuthId;
+	type FeederOrigin = frame_system::EnsureRoot<AccountId>;
+	type MaxFeeders = MaxFeeders;
+	type MaxPriceAge = MaxPriceAge;
+	type FeedInterval = FeedInterval;
+}
+
+impl frame_system::offchain::SigningTypes for Runtime {
+	type Public = <Signature as Verify>::Signer;
+	type Signature = Signature;
+}
+
+impl<C> frame_system::offchain::SendTransactionTypes<C> for Runtime
+where
+	Call: From<C>,
+{
+	type OverarchingCall = Call;
+	type Extrinsic = UncheckedExtrinsic;
+}
+
+impl<LocalCall> frame_system::offchain::CreateSignedTransaction<LocalCall> for Runtime
+where
+	Call: From<LocalCall>,
+{
+	fn create_transaction<C: frame_system::offchain::AppCrypto<Self::Public, Self::Signature>>(
+		call: Call,
+		public: <Signature as Verify>::Signer,
+		account: AccountId,
+		nonce: Index,
+	) -> Option<(Call, <UncheckedExtrinsic as sp_runtime::traits::Extrinsic>::SignaturePayload)> {
+		// Offchain transactions stay valid for a limited number of blocks only.
+		let period = BlockHashCount::get()
+			.checked_next_power_of_two()
+			.map(|c| c / 2)
+			.unwrap_or(2) as u64;
+		let current_block = System::block_number()
+			.saturated_into::<u64>()
+			.saturating_sub(1);
+		let extra: SignedExtra = (
+			frame_system::CheckSpecVersion::<Runtime>::new(),
+			frame_system::CheckTxVersion::<Runtime>::new(),
+			frame_system::CheckGenesis::<Runtime>::new(),
+			frame_system::CheckEra::<Runtime>::from(generic::Era::mortal(period, current_block)),
+			frame_system::CheckNonce::<Runtime>::from(nonce),
+			frame_system::CheckWeight::<Runtime>::new(),
+			pallet_transaction_payment::ChargeTransactionPayment::<Runtime>::from(0),
+		);
+		let raw_payload = generic::SignedPayload::new(call, extra)
+			.map_err(|e| {
+				frame_support::debug::warn!("Unable to create signed payload: {:?}", e);
+			})
+			.ok()?;
+		let signature = raw_payload.using_encoded(|payload| C::sign(payload, public))?;
+		let (call, extra, _) = raw_payload.deconstruct();
+		Some((call, (sp_runtime::MultiAddress::Id(account), signature, extra)))
+	}
+}
+
+/// Routes the single-asset trades of the stone index pallet through the DEX.
+pub struct DexSwap;
+impl pallet_stone_index::Swap<AccountId, u32, Balance> for DexSwap {
+	fn swap_exact_out(
+		who: &AccountId,
+		asset_in: u32,
+		asset_out: u32,
+		amount_out: Balance,
+		max_amount_in: Balance,
+	) -> Result<Balance, DispatchError> {
+		Dex::do_swap_exact_out(who, asset_in, asset_out, amount_out, max_amount_in)
+	}
+
+	fn swap_exact_in(
+		who: &AccountId,
+		asset_in: u32,
+		asset_out: u32,
+		amount_in: Balance,
+		min_amount_out: Balance,
+	) -> Result<Balance, DispatchError> {
+		Dex::do_swap_exact_in(who, asset_in, asset_out, amount_in, min_amount_out)
+	}
 }
 
 // Create the runtime by composing the FRAME pallets that were previously configured.
@@ -295,6 +443,8 @@ construct_runtime!(
 		// Include the custom logic from the pallet-template in the runtime.
 		Assets: pallet_assets::{Module, Call, Storage, Event<T>},
 		StoneIndex: pallet_stone_index::{Module, Call, Storage, Event<T>},
+		Dex: pallet_dex::{Module, Call, Storage, Event<T>},
+		Oracle: pallet_oracle::{Module, Call, Storage, Config<T>, Event<T>},
 	}
 );
 